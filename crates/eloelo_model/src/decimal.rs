@@ -1,45 +1,209 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Most fractional digits a decimal can have.
+const MAX_SCALE: u32 = 12;
+/// Fractional digits of a quotient, unless operands have more.
+const DIVISION_SCALE: u32 = 9;
+
+/// Decimal number stored exactly as `mantissa * 10^-scale`.
+///
+/// Serialized as a string, e.g. `"0.75"`, keeping the fractional digits it was created with.
+/// Comparison is by value, so `"0.1"` equals `"0.10"`. Operators panic on overflow, like
+/// integer ones.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
 
-#[derive(Clone, Debug, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Decimal(String);
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid decimal: {0}")]
+pub struct ParseDecimalError(String);
 
 impl Decimal {
+    pub const ZERO: Decimal = Decimal {
+        mantissa: 0,
+        scale: 0,
+    };
+
     /// Panics when literal does not represent a correct decimal.
     pub fn new(literal: &str) -> Self {
-        Decimal(literal.into())
-            .invariant_ensured()
-            .expect("Invalid decimal literal")
+        literal.parse().expect("Invalid decimal literal")
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    pub fn as_f32(&self) -> f32 {
+        self.as_f64() as f32
+    }
+
+    /// Values that cannot be represented, i.e. non-finite or huge ones, become zero.
+    pub fn with_precision(value: f64, precision: usize) -> Self {
+        let precision = precision.min(MAX_SCALE as usize);
+        format!("{value:.0$}", precision)
+            .parse()
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    /// Rounds half away from zero when precision is reduced.
+    pub fn truncate_to_precision(&self, precision: usize) -> Self {
+        let precision = (precision as u32).min(MAX_SCALE);
+        self.rescaled(precision)
     }
 
-    fn invariant_ensured(self) -> Option<Self> {
-        let v = self.as_f64();
-        if v.is_nan() || v.is_infinite() {
-            None
+    fn rescaled(&self, scale: u32) -> Self {
+        let mantissa = if scale >= self.scale {
+            checked(self.mantissa.checked_mul(pow10(scale - self.scale)))
         } else {
-            Some(self)
+            div_round(self.mantissa, pow10(self.scale - scale))
+        };
+        Decimal { mantissa, scale }
+    }
+
+    /// Integer part rounded toward negative infinity and the remaining fraction as an integer
+    /// with `scale` digits, `scale` being at least the scale of the decimal.
+    fn parts(&self, scale: u32) -> (i128, i128) {
+        let pow = pow10(self.scale);
+        (
+            self.mantissa.div_euclid(pow),
+            self.mantissa.rem_euclid(pow) * pow10(scale - self.scale),
+        )
+    }
+
+    /// Strips trailing fractional zeros, keeping at least `min_scale` digits.
+    fn trimmed(mut self, min_scale: u32) -> Self {
+        while self.scale > min_scale && self.mantissa % 10 == 0 {
+            self.mantissa /= 10;
+            self.scale -= 1;
         }
+        self
     }
+}
 
-    pub fn as_f64(&self) -> f64 {
-        self.0.parse().expect("invalid decimal")
+fn pow10(exp: u32) -> i128 {
+    checked(10i128.checked_pow(exp))
+}
+
+fn checked(value: Option<i128>) -> i128 {
+    value.expect("Decimal overflow")
+}
+
+/// Division rounding half away from zero.
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    if remainder.unsigned_abs() * 2 >= denominator.unsigned_abs() {
+        quotient + numerator.signum() * denominator.signum()
+    } else {
+        quotient
     }
+}
 
-    pub fn as_f32(&self) -> f32 {
-        self.0.parse().expect("invalid decimal")
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseDecimalError(s.to_string());
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        // Scientific notation is what f64 values were sometimes written as
+        let (digits, exponent) = match digits.split_once(['e', 'E']) {
+            Some((digits, exponent)) => (digits, exponent.parse().map_err(|_| invalid())?),
+            None => (digits, 0i64),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let all_digits = integer
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit());
+        if !all_digits || (integer.is_empty() && fraction.is_empty()) {
+            return Err(invalid());
+        }
+        let scale = (fraction.len() as i64)
+            .checked_sub(exponent)
+            .ok_or_else(invalid)?;
+        let digits = format!("{integer}{fraction}");
+        if scale > MAX_SCALE as i64 {
+            // Values written from f64 can have more digits, they are rounded half away from
+            // zero like in `truncate_to_precision`. Digits beyond all the written ones are zeros.
+            let (kept, dropped) = usize::try_from(scale - MAX_SCALE as i64)
+                .ok()
+                .and_then(|excess| digits.len().checked_sub(excess))
+                .map_or(("", ""), |kept| digits.split_at(kept));
+            let round_up = dropped.bytes().next().is_some_and(|d| d >= b'5');
+            let mantissa = match kept {
+                "" => 0,
+                kept => kept.parse::<i128>().map_err(|_| invalid())?,
+            } + i128::from(round_up);
+            let decimal = Decimal {
+                mantissa: if negative { -mantissa } else { mantissa },
+                scale: MAX_SCALE,
+            };
+            return Ok(decimal.trimmed(0));
+        }
+        let mut mantissa: i128 = digits.parse().map_err(|_| invalid())?;
+        if scale < 0 {
+            mantissa = u32::try_from(-scale)
+                .ok()
+                .and_then(|exp| 10i128.checked_pow(exp))
+                .and_then(|pow| mantissa.checked_mul(pow))
+                .ok_or_else(invalid)?;
+        }
+        Ok(Decimal {
+            mantissa: if negative { -mantissa } else { mantissa },
+            scale: scale.max(0) as u32,
+        })
     }
+}
 
-    pub fn with_precision(value: f64, precision: usize) -> Self {
-        Decimal(format!("{value:.0$}", precision))
+impl TryFrom<&str> for Decimal {
+    type Error = ParseDecimalError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
     }
+}
+
+impl TryFrom<String> for Decimal {
+    type Error = ParseDecimalError;
 
-    pub fn as_str(&self) -> &str {
-        &self.0
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
     }
+}
 
-    pub fn truncate_to_precision(&self, precision: usize) -> Self {
-        // Likely this is prone to numerical errors but don't care until problematic.
-        Decimal::with_precision(self.as_f64(), precision)
+impl From<Decimal> for String {
+    fn from(value: Decimal) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let abs = self.mantissa.unsigned_abs();
+        if self.scale == 0 {
+            return write!(f, "{sign}{abs}");
+        }
+        let pow = 10u128.pow(self.scale);
+        write!(
+            f,
+            "{sign}{}.{:02$}",
+            abs / pow,
+            abs % pow,
+            self.scale as usize
+        )
     }
 }
 
@@ -57,7 +221,94 @@ impl Into<f32> for Decimal {
 
 impl PartialEq for Decimal {
     fn eq(&self, other: &Self) -> bool {
-        self.as_f64() == other.as_f64()
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    // Rescaling whole mantissas could overflow, parts with at most MAX_SCALE digits cannot.
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.parts(scale).cmp(&other.parts(scale))
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Self::Output {
+        Decimal {
+            mantissa: -self.mantissa,
+            scale: self.scale,
+        }
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let scale = self.scale.max(rhs.scale);
+        Decimal {
+            mantissa: checked(
+                self.rescaled(scale)
+                    .mantissa
+                    .checked_add(rhs.rescaled(scale).mantissa),
+            ),
+            scale,
+        }
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+
+    /// Keeps the digits of both operands, up to the maximum scale.
+    // Scales of the operands add up in the product.
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, rhs: Self) -> Self::Output {
+        let product = Decimal {
+            mantissa: checked(self.mantissa.checked_mul(rhs.mantissa)),
+            scale: self.scale + rhs.scale,
+        };
+        product.rescaled(product.scale.min(MAX_SCALE))
+    }
+}
+
+impl Div for Decimal {
+    type Output = Decimal;
+
+    /// Rounds the quotient to 9 fractional digits, or more when operands have them. Trailing
+    /// zeros beyond the digits of operands are stripped. Panics on division by zero.
+    fn div(self, rhs: Self) -> Self::Output {
+        assert!(rhs.mantissa != 0, "Decimal division by zero");
+        let operand_scale = self.scale.max(rhs.scale);
+        let scale = operand_scale.max(DIVISION_SCALE);
+        let numerator = checked(
+            self.mantissa
+                .checked_mul(pow10(scale + rhs.scale - self.scale)),
+        );
+        Decimal {
+            mantissa: div_round(numerator, rhs.mantissa),
+            scale,
+        }
+        .trimmed(operand_scale)
     }
 }
 
@@ -68,9 +319,17 @@ mod tests {
 
     #[test]
     fn with_precision() {
-        assert_eq!(Decimal::with_precision(1.11111111111, 1).as_str(), "1.1");
-        assert_eq!(Decimal::with_precision(1.11111111111, 2).as_str(), "1.11");
-        assert_eq!(Decimal::with_precision(1.11111111111, 3).as_str(), "1.111");
+        assert_eq!(Decimal::with_precision(1.11111111111, 1).to_string(), "1.1");
+        assert_eq!(
+            Decimal::with_precision(1.11111111111, 2).to_string(),
+            "1.11"
+        );
+        assert_eq!(
+            Decimal::with_precision(1.11111111111, 3).to_string(),
+            "1.111"
+        );
+        assert_eq!(Decimal::with_precision(-0.02, 2).to_string(), "-0.02");
+        assert_eq!(Decimal::with_precision(f64::NAN, 2), Decimal::ZERO);
     }
 
     #[test]
@@ -79,6 +338,10 @@ mod tests {
             serde_json::to_value(Decimal::with_precision(37.21, 2))?,
             serde_json::Value::String("37.21".into())
         );
+        assert_eq!(
+            serde_json::to_value(Decimal::new("0.10"))?,
+            serde_json::Value::String("0.10".into())
+        );
         Ok(())
     }
 
@@ -88,9 +351,43 @@ mod tests {
             serde_json::from_str::<Decimal>("\"21.37\"")?,
             Decimal::new("21.37")
         );
+        for invalid in ["\"NaN\"", "\"abc\"", "\"+inf\"", "\"\"", "\"1.2.3\"", "0.5"] {
+            assert!(
+                serde_json::from_str::<Decimal>(invalid).is_err(),
+                "{invalid}"
+            );
+        }
         Ok(())
     }
 
+    #[test]
+    fn parse() {
+        assert_eq!("-.5".parse(), Ok(Decimal::new("-0.5")));
+        assert_eq!("+3".parse(), Ok(Decimal::new("3")));
+        assert_eq!("1e3".parse(), Ok(Decimal::new("1000")));
+        assert_eq!("-2.5E-3".parse(), Ok(Decimal::new("-0.0025")));
+        assert_eq!("1.0e+1".parse::<Decimal>().unwrap().to_string(), "10");
+        assert_eq!(
+            Decimal::try_from("1e").unwrap_err().to_string(),
+            "Invalid decimal: 1e"
+        );
+        assert!(Decimal::try_from(String::from("-")).is_err());
+        assert!(Decimal::try_from("1e40").is_err());
+        assert!(Decimal::try_from("1e-9223372036854775808").is_err());
+        assert!(Decimal::try_from("1e9223372036854775807").is_err());
+    }
+
+    #[test]
+    fn parse_rounds_to_max_scale() {
+        let parsed = |s: &str| s.parse::<Decimal>().unwrap().to_string();
+        assert_eq!(parsed("0.5000000000000"), "0.5");
+        assert_eq!(parsed("0.30000000000000004"), "0.3");
+        assert_eq!(parsed("-0.1234567890125"), "-0.123456789013");
+        assert_eq!(parsed("0.0000000000001"), "0");
+        assert_eq!(parsed("5e-13"), "0.000000000001");
+        assert_eq!(parsed("1e-9223372036854775807"), "0");
+    }
+
     #[test]
     fn eq() -> Result<()> {
         assert_eq!(Decimal::new("0.1"), Decimal::new("0.1"));
@@ -99,6 +396,43 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn ord() {
+        let mut values: Vec<_> = ["0.5", "-1", "0.05", "0.50", "10"]
+            .into_iter()
+            .map(Decimal::new)
+            .collect();
+        values.sort();
+        let sorted: Vec<_> = values.iter().map(Decimal::to_string).collect();
+        assert_eq!(sorted, ["-1", "0.05", "0.5", "0.50", "10"]);
+        assert!(Decimal::new("-0.02") < Decimal::ZERO);
+
+        let max = Decimal::new(&i128::MAX.to_string());
+        assert!(max > Decimal::new("0.5"));
+        assert!(-max.clone() < Decimal::new("-0.5"));
+        assert_ne!(max, Decimal::new("0.000000000001"));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let d = Decimal::new;
+        assert_eq!((d("0.1") + d("0.2")).to_string(), "0.3");
+        assert_eq!((d("1.5") - d("0.25")).to_string(), "1.25");
+        assert_eq!((d("1.5") * d("-0.2")).to_string(), "-0.30");
+        assert_eq!((d("1") / d("4")).to_string(), "0.25");
+        assert_eq!((d("2") / d("3")).to_string(), "0.666666667");
+        assert_eq!((-d("0.75")).to_string(), "-0.75");
+        assert_eq!(d("0.125").truncate_to_precision(2).to_string(), "0.13");
+        assert_eq!(d("-0.125").truncate_to_precision(2).to_string(), "-0.13");
+        assert_eq!(d("0.5").truncate_to_precision(3).to_string(), "0.500");
+    }
+
+    #[test]
+    #[should_panic]
+    fn division_by_zero() {
+        let _d = Decimal::new("1") / Decimal::ZERO;
+    }
+
     #[test]
     #[should_panic]
     fn nan() {
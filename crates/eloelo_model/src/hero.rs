@@ -0,0 +1,70 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Dota heroes, one `name,tag` pair per line.
+pub const HEROES_CSV: &str = include_str!("dota_heroes.csv");
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Hero(String);
+
+#[derive(Error, Debug)]
+#[error("Incorrect hero name: \"{0}\". See `all` for list of valid names.")]
+pub struct InvalidHero(String);
+
+impl Hero {
+    /// Panics when name is not a known hero.
+    pub fn new(name: &str) -> Self {
+        Hero::try_from(String::from(name)).unwrap()
+    }
+
+    pub fn all() -> HashSet<Hero> {
+        HEROES_CSV
+            .split("\n")
+            .map(|s| Hero(String::from(s.split(',').next().unwrap().trim())))
+            .collect()
+    }
+
+    pub fn all_alphabetical() -> Vec<Hero> {
+        let mut heroes: Vec<_> = Hero::all().into_iter().collect();
+        heroes.sort();
+        heroes
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Hero {
+    type Error = InvalidHero;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if Hero::all().contains(value.as_str()) {
+            Ok(Hero(value))
+        } else {
+            Err(InvalidHero(value))
+        }
+    }
+}
+
+impl Display for Hero {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Borrow<str> for Hero {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<Hero> for Hero {
+    fn as_ref(&self) -> &Hero {
+        self
+    }
+}
@@ -1,19 +1,68 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Display;
 use std::time::Duration;
 use std::{borrow::Borrow, collections::HashMap};
 
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, Utc};
 use log::error;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::{GameId, PlayerId, WinScale};
+use crate::hero::Hero;
+use crate::{GameId, PlayerId, Team, WinScale};
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct History {
     pub entries: HashMap<GameId, Vec<HistoryEntry>>,
 }
 
+/// Contents of a single `*.history.json` file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistorySerializeWrapper {
+    pub game: GameId,
+    pub entries: Vec<HistoryEntry>,
+}
+
+/// Identifies a match across all games, derived from its timestamp.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub struct MatchId(String);
+
+impl MatchId {
+    pub fn from_timestamp(timestamp: &DateTime<Local>) -> Self {
+        MatchId(
+            timestamp
+                .with_timezone(&Utc)
+                .format("%Y%m%d-%H%M%S-%3f")
+                .to_string(),
+        )
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Display for MatchId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<&str> for MatchId {
+    fn from(value: &str) -> Self {
+        MatchId(value.to_string())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct HistoryEntry {
+    /// Missing in entries recorded before ids were introduced, see [`assign_missing_ids`]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "MatchId::is_empty")]
+    pub id: MatchId,
     pub timestamp: DateTime<Local>,
     pub winner: Vec<PlayerId>,
     pub loser: Vec<PlayerId>,
@@ -26,6 +75,22 @@ pub struct HistoryEntry {
     #[serde(default)]
     #[serde(skip_serializing_if = "is_default")]
     pub fake: bool,
+    /// Seed of the shuffle and hero assignment of this match
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Side the winning team played on, the losing team played on the other one
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winner_side: Option<Team>,
+    /// Hero played by each player, for games that have heroes
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub heroes: BTreeMap<PlayerId, Hero>,
+    /// Heroes DotaBot offered to each player, including rerolls
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub offered_heroes: BTreeMap<PlayerId, Vec<Hero>>,
 }
 
 fn is_default<T: Default + PartialEq<T>>(v: &T) -> bool {
@@ -48,10 +113,108 @@ where
     Ok(Duration::from_secs(seconds))
 }
 
+/// Gives entries without an id one derived from their timestamp. Matches recorded within
+/// the same millisecond get a numeric suffix, so ids stay unique and stable across loads.
+pub fn assign_missing_ids(entries: &mut [HistoryEntry]) {
+    let mut taken: HashSet<MatchId> = entries.iter().map(|e| e.id.clone()).collect();
+    for entry in entries.iter_mut().filter(|e| e.id.is_empty()) {
+        let base = MatchId::from_timestamp(&entry.timestamp);
+        let mut id = base.clone();
+        let mut n = 1;
+        while taken.contains(&id) {
+            n += 1;
+            id = MatchId(format!("{base}-{n}"));
+        }
+        taken.insert(id.clone());
+        entry.id = id;
+    }
+}
+
+/// Audited change of a recorded match, kept in a `*.audit.json` file next to the history.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryCorrection {
+    pub match_id: MatchId,
+    pub timestamp: DateTime<Local>,
+    pub author: String,
+    pub reason: String,
+    pub previous: HistoryEntry,
+    /// Entry after the change, or none when the match was voided
+    pub current: Option<HistoryEntry>,
+}
+
+/// Changes of a recorded match, fields left unset keep their recorded value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryAmendment {
+    /// Swaps winner and loser, fixing a misreported result
+    pub swap_winner: bool,
+    pub scale: Option<WinScale>,
+    pub duration: Option<Duration>,
+    pub fake: Option<bool>,
+    /// Replaces heroes of the given players
+    pub heroes: HashMap<PlayerId, Hero>,
+}
+
+/// Contents of a single `*.audit.json` file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryAudit {
+    pub game: GameId,
+    pub corrections: Vec<HistoryCorrection>,
+}
+
 impl HistoryEntry {
     pub fn all_players(&self) -> impl Iterator<Item = &PlayerId> {
         self.winner.iter().chain(self.loser.iter())
     }
+
+    pub fn loser_side(&self) -> Option<Team> {
+        self.winner_side.map(Team::opposite)
+    }
+
+    pub fn amended(&self, amendment: &HistoryAmendment) -> HistoryEntry {
+        let mut entry = self.clone();
+        if amendment.swap_winner {
+            std::mem::swap(&mut entry.winner, &mut entry.loser);
+            entry.winner_side = entry.loser_side();
+        }
+        entry.scale = amendment.scale.unwrap_or(entry.scale);
+        entry.duration = amendment.duration.unwrap_or(entry.duration);
+        entry.fake = amendment.fake.unwrap_or(entry.fake);
+        entry.heroes.extend(amendment.heroes.clone());
+        entry
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl HistoryEntry {
+    /// Even, 40 minute match without an id, played `seconds` after the epoch.
+    ///
+    /// Meant for tests, which set other fields with struct update syntax.
+    pub fn test_entry(
+        seconds: i64,
+        winner: impl IntoIterator<Item = impl AsRef<str>>,
+        loser: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Self {
+        fn players(team: impl IntoIterator<Item = impl AsRef<str>>) -> Vec<PlayerId> {
+            team.into_iter()
+                .map(|p| PlayerId::from(p.as_ref()))
+                .collect()
+        }
+        HistoryEntry {
+            id: Default::default(),
+            timestamp: DateTime::<Utc>::from_timestamp(seconds, 0)
+                .expect("Test timestamp out of range")
+                .into(),
+            winner: players(winner),
+            loser: players(loser),
+            scale: WinScale::Even,
+            duration: Duration::from_secs(40 * 60),
+            fake: false,
+            seed: None,
+            winner_side: None,
+            heroes: Default::default(),
+            offered_heroes: Default::default(),
+        }
+    }
 }
 
 impl History {
@@ -92,8 +255,6 @@ impl History {
 
 #[cfg(test)]
 mod tests {
-    use chrono::Utc;
-
     use super::*;
 
     fn make_entry(
@@ -101,14 +262,7 @@ mod tests {
         winner: impl IntoIterator<Item = &'static str>,
         loser: impl IntoIterator<Item = &'static str>,
     ) -> HistoryEntry {
-        HistoryEntry {
-            timestamp: DateTime::<Utc>::from_timestamp(time, 0).unwrap().into(),
-            winner: winner.into_iter().map(PlayerId::from).collect(),
-            loser: loser.into_iter().map(PlayerId::from).collect(),
-            scale: WinScale::Even,
-            duration: Duration::from_secs(40 * 60),
-            fake: false,
-        }
+        HistoryEntry::test_entry(time, winner, loser)
     }
 
     #[test]
@@ -138,4 +292,62 @@ mod tests {
         let streaks = history.calculate_lose_streaks(&game_id, players, Some(1));
         assert_eq!(streaks.get(&PlayerId::from("j")).copied(), Some(0));
     }
+
+    #[test]
+    fn amended() {
+        let entry = HistoryEntry {
+            winner_side: Some(Team::Left),
+            ..make_entry(1, ["bixkog", "spawek"], ["j"])
+        };
+        let amended = entry.amended(&HistoryAmendment {
+            swap_winner: true,
+            scale: Some(WinScale::Pwnage),
+            ..Default::default()
+        });
+        assert_eq!(amended.winner, [PlayerId::from("j")]);
+        assert_eq!(amended.loser.len(), 2);
+        assert_eq!(amended.winner_side, Some(Team::Right));
+        assert_eq!(amended.scale, WinScale::Pwnage);
+        assert_eq!(amended.duration, entry.duration);
+        assert_eq!(amended.id, entry.id);
+    }
+
+    #[test]
+    fn assign_missing_ids() {
+        let mut entries = vec![make_entry(1, ["a"], ["b"]), make_entry(1, ["b"], ["a"])];
+        entries.push(HistoryEntry {
+            id: MatchId::from("custom"),
+            ..make_entry(2, ["a"], ["b"])
+        });
+        super::assign_missing_ids(&mut entries);
+        let ids: Vec<_> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["19700101-000001-000", "19700101-000001-000-2", "custom"]
+        );
+    }
+
+    #[test]
+    fn heroes_are_optional() -> anyhow::Result<()> {
+        let json = r#"{"timestamp":"2024-01-01T20:00:00+01:00","winner":["bixkog"],"loser":["j"]}"#;
+        let entry: HistoryEntry = serde_json::from_str(json)?;
+        assert!(entry.heroes.is_empty());
+        assert!(entry.offered_heroes.is_empty());
+        let serialized = serde_json::to_value(&entry)?;
+        assert!(serialized.get("heroes").is_none());
+        assert!(serialized.get("offered_heroes").is_none());
+
+        let entry = HistoryEntry {
+            heroes: BTreeMap::from([(PlayerId::from("j"), Hero::new("Axe"))]),
+            offered_heroes: BTreeMap::from([(
+                PlayerId::from("j"),
+                vec![Hero::new("Axe"), Hero::new("Pudge")],
+            )]),
+            ..entry
+        };
+        let serialized = serde_json::to_value(&entry)?;
+        assert_eq!(serialized["heroes"]["j"], "Axe");
+        assert_eq!(serde_json::from_value::<HistoryEntry>(serialized)?, entry);
+        Ok(())
+    }
 }
@@ -0,0 +1,241 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::history::{History, HistoryEntry, MatchId};
+use crate::{GameId, PlayerId, WinScale};
+
+/// Filters of [`History::query`], unset ones match every entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryQuery {
+    pub game: GameId,
+    /// Matches the player took part in, on either side
+    pub player: Option<PlayerId>,
+    /// Inclusive
+    pub from: Option<DateTime<Local>>,
+    /// Exclusive
+    pub to: Option<DateTime<Local>>,
+    pub fake: Option<bool>,
+    /// Fake matches played earlier are left out
+    pub fakes_since: Option<DateTime<Local>>,
+    pub scale: Option<WinScale>,
+    /// Continues after the last entry of a previous page
+    pub cursor: Option<HistoryCursor>,
+    pub limit: usize,
+}
+
+/// Position in history ordered from the newest match.
+///
+/// Refers to the timestamp and id rather than an index, so pages stay consistent when
+/// matches are added or voided in between.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HistoryCursor {
+    timestamp: DateTime<Local>,
+    id: MatchId,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid history cursor: {0}")]
+pub struct InvalidCursor(String);
+
+pub struct HistoryPage<'a> {
+    /// Newest first
+    pub entries: Vec<&'a HistoryEntry>,
+    /// Set when there are more matching entries
+    pub next_cursor: Option<HistoryCursor>,
+}
+
+impl HistoryCursor {
+    fn of(entry: &HistoryEntry) -> Self {
+        HistoryCursor {
+            timestamp: entry.timestamp,
+            id: entry.id.clone(),
+        }
+    }
+}
+
+impl Display for HistoryCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let nanos = self.timestamp.timestamp_nanos_opt().unwrap_or_default();
+        write!(f, "{nanos}_{}", self.id)
+    }
+}
+
+impl FromStr for HistoryCursor {
+    type Err = InvalidCursor;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidCursor(s.to_string());
+        let (nanos, id) = s.split_once('_').ok_or_else(invalid)?;
+        let nanos: i64 = nanos.parse().map_err(|_| invalid())?;
+        Ok(HistoryCursor {
+            timestamp: DateTime::from_timestamp_nanos(nanos).into(),
+            id: MatchId::from(id),
+        })
+    }
+}
+
+impl TryFrom<String> for HistoryCursor {
+    type Error = InvalidCursor;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<HistoryCursor> for String {
+    fn from(value: HistoryCursor) -> Self {
+        value.to_string()
+    }
+}
+
+impl HistoryQuery {
+    pub fn new(game: GameId, limit: usize) -> Self {
+        HistoryQuery {
+            game,
+            player: None,
+            from: None,
+            to: None,
+            fake: None,
+            fakes_since: None,
+            scale: None,
+            cursor: None,
+            limit,
+        }
+    }
+
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.player
+            .as_ref()
+            .is_none_or(|p| entry.all_players().any(|e| e == p))
+            && self.from.is_none_or(|from| entry.timestamp >= from)
+            && self.to.is_none_or(|to| entry.timestamp < to)
+            && self.fake.is_none_or(|fake| entry.fake == fake)
+            && self
+                .fakes_since
+                .is_none_or(|since| !entry.fake || entry.timestamp >= since)
+            && self.scale.is_none_or(|scale| entry.scale == scale)
+            && self
+                .cursor
+                .as_ref()
+                .is_none_or(|cursor| HistoryCursor::of(entry) < *cursor)
+    }
+}
+
+impl History {
+    pub fn query(&self, query: &HistoryQuery) -> HistoryPage<'_> {
+        let mut entries: Vec<_> = self
+            .entries
+            .get(&query.game)
+            .into_iter()
+            .flatten()
+            .filter(|e| query.matches(e))
+            .collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(HistoryCursor::of(e)));
+        let next_cursor = if entries.len() > query.limit {
+            entries.truncate(query.limit);
+            entries.last().map(|e| HistoryCursor::of(e))
+        } else {
+            None
+        };
+        HistoryPage {
+            entries,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Utc;
+
+    use super::*;
+
+    fn make_entry(time: i64, winner: &str, loser: &str, fake: bool) -> HistoryEntry {
+        let entry = HistoryEntry::test_entry(time, [winner], [loser]);
+        HistoryEntry {
+            id: MatchId::from_timestamp(&entry.timestamp),
+            fake,
+            ..entry
+        }
+    }
+
+    fn history() -> History {
+        History {
+            entries: HashMap::from([(
+                GameId::from("game"),
+                vec![
+                    make_entry(1, "a", "b", false),
+                    make_entry(2, "b", "c", true),
+                    make_entry(3, "a", "c", false),
+                    make_entry(4, "c", "b", false),
+                    make_entry(5, "a", "b", false),
+                ],
+            )]),
+        }
+    }
+
+    fn times(page: &HistoryPage) -> Vec<i64> {
+        page.entries
+            .iter()
+            .map(|e| e.timestamp.timestamp())
+            .collect()
+    }
+
+    #[test]
+    fn pagination() {
+        let history = history();
+        let mut query = HistoryQuery::new(GameId::from("game"), 2);
+        let page = history.query(&query);
+        assert_eq!(times(&page), [5, 4]);
+
+        query.cursor = page.next_cursor;
+        let page = history.query(&query);
+        assert_eq!(times(&page), [3, 2]);
+
+        query.cursor = page.next_cursor;
+        let page = history.query(&query);
+        assert_eq!(times(&page), [1]);
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[test]
+    fn filters() {
+        let history = history();
+        let query = HistoryQuery {
+            player: Some(PlayerId::from("a")),
+            ..HistoryQuery::new(GameId::from("game"), 10)
+        };
+        assert_eq!(times(&history.query(&query)), [5, 3, 1]);
+
+        let query = HistoryQuery {
+            from: Some(DateTime::<Utc>::from_timestamp(2, 0).unwrap().into()),
+            to: Some(DateTime::<Utc>::from_timestamp(4, 0).unwrap().into()),
+            fake: Some(false),
+            ..HistoryQuery::new(GameId::from("game"), 10)
+        };
+        assert_eq!(times(&history.query(&query)), [3]);
+
+        let query = HistoryQuery {
+            fakes_since: Some(DateTime::<Utc>::from_timestamp(3, 0).unwrap().into()),
+            ..HistoryQuery::new(GameId::from("game"), 10)
+        };
+        assert_eq!(times(&history.query(&query)), [5, 4, 3, 1]);
+
+        let query = HistoryQuery::new(GameId::from("other"), 10);
+        assert!(history.query(&query).entries.is_empty());
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = HistoryCursor::of(&make_entry(1, "a", "b", false));
+        assert_eq!(cursor.to_string().parse(), Ok(cursor));
+        assert!("abc".parse::<HistoryCursor>().is_err());
+    }
+}
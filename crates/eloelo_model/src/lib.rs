@@ -4,9 +4,13 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 pub mod decimal;
+pub mod hero;
 pub mod history;
+pub mod history_query;
+pub mod migration;
 pub mod options;
 pub mod player;
+pub mod stats;
 
 #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -16,7 +20,7 @@ pub enum GameState {
     MatchInProgress,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Team {
     Left,
@@ -24,6 +28,13 @@ pub enum Team {
 }
 
 impl Team {
+    pub fn opposite(self) -> Self {
+        match self {
+            Team::Left => Team::Right,
+            Team::Right => Team::Left,
+        }
+    }
+
     pub fn from_str(value: &str) -> Option<Self> {
         match value.to_lowercase().as_str() {
             "left" => Some(Team::Left),
@@ -166,6 +177,9 @@ pub struct BalancedTeam {
     pub pity_elo: i32,
     #[serde(default)]
     pub real_elo: i32,
+    /// Players whose lose streaks gave the team its pity bonus
+    #[serde(default)]
+    pub pity_contributors: Vec<PlayerId>,
 }
 
 #[cfg(test)]
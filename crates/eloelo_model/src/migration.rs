@@ -0,0 +1,225 @@
+// Schema versions of persisted files and migrations between them
+
+use log::info;
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::history::{assign_missing_ids, HistorySerializeWrapper};
+
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Upgrades a file by one version, from `from` to `from + 1`.
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(&mut Value) -> serde_json::Result<()>,
+}
+
+/// Versions of one kind of persisted file. Files written before versioning are version 0.
+pub struct Schema {
+    pub name: &'static str,
+    /// Ordered by `from`, starting with 0
+    pub migrations: &'static [Migration],
+}
+
+impl Schema {
+    pub fn current_version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+}
+
+pub const HISTORY: Schema = Schema {
+    name: "history",
+    migrations: &[Migration {
+        from: 0,
+        description: "Store match ids",
+        apply: store_match_ids,
+    }],
+};
+
+pub const AUDIT: Schema = Schema {
+    name: "history audit",
+    migrations: &[Migration {
+        from: 0,
+        description: "Add schema version",
+        apply: no_change,
+    }],
+};
+
+pub const STATE: Schema = Schema {
+    name: "state",
+    migrations: &[Migration {
+        from: 0,
+        description: "Add schema version",
+        apply: no_change,
+    }],
+};
+
+pub const BOT_STATE: Schema = Schema {
+    name: "bot state",
+    migrations: &[Migration {
+        from: 0,
+        description: "Add schema version",
+        apply: no_change,
+    }],
+};
+
+pub const PLAYERS: Schema = Schema {
+    name: "players",
+    migrations: &[Migration {
+        from: 0,
+        description: "Add schema version",
+        apply: no_change,
+    }],
+};
+
+#[cfg(test)]
+const ALL: [&Schema; 5] = [&HISTORY, &AUDIT, &STATE, &BOT_STATE, &PLAYERS];
+
+/// Data written together with the current version of its schema.
+#[derive(Serialize)]
+pub struct Versioned<'a, T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+impl<'a, T> Versioned<'a, T> {
+    pub fn new(schema: &Schema, data: &'a T) -> Self {
+        Versioned {
+            schema_version: schema.current_version(),
+            data,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("Invalid schema version: {0}")]
+    InvalidVersion(Value),
+    #[error("{schema} schema version {version} is newer than supported version {current}")]
+    NewerVersion {
+        schema: &'static str,
+        version: u32,
+        current: u32,
+    },
+    #[error("{schema} migration from version {from} failed: {source}")]
+    Failed {
+        schema: &'static str,
+        from: u32,
+        source: serde_json::Error,
+    },
+}
+
+pub fn schema_version(value: &Value) -> Result<u32, MigrationError> {
+    match value.get(SCHEMA_VERSION_KEY) {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| MigrationError::InvalidVersion(version.clone())),
+    }
+}
+
+/// Fails for files written by a newer version of the application, which could lose data
+/// when stored again.
+pub fn ensure_supported(schema: &Schema, version: u32) -> Result<(), MigrationError> {
+    let current = schema.current_version();
+    if version > current {
+        return Err(MigrationError::NewerVersion {
+            schema: schema.name,
+            version,
+            current,
+        });
+    }
+    Ok(())
+}
+
+/// Upgrades `value` from its version to the current one.
+pub fn migrate(schema: &Schema, value: &mut Value) -> Result<(), MigrationError> {
+    let version = schema_version(value)?;
+    ensure_supported(schema, version)?;
+    for migration in &schema.migrations[version as usize..] {
+        info!(
+            "Migrating {} from version {}: {}",
+            schema.name, migration.from, migration.description
+        );
+        (migration.apply)(value).map_err(|source| MigrationError::Failed {
+            schema: schema.name,
+            from: migration.from,
+            source,
+        })?;
+    }
+    if let Value::Object(map) = value {
+        map.insert(
+            SCHEMA_VERSION_KEY.to_string(),
+            schema.current_version().into(),
+        );
+    }
+    Ok(())
+}
+
+fn no_change(_: &mut Value) -> serde_json::Result<()> {
+    Ok(())
+}
+
+fn store_match_ids(value: &mut Value) -> serde_json::Result<()> {
+    let mut history: HistorySerializeWrapper = serde_json::from_value(value.take())?;
+    assign_missing_ids(&mut history.entries);
+    *value = serde_json::to_value(history)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn migrations_are_ordered() {
+        for schema in ALL {
+            for (i, migration) in schema.migrations.iter().enumerate() {
+                assert_eq!(migration.from, i as u32, "{}", schema.name);
+            }
+        }
+    }
+
+    #[test]
+    fn migrate_history() -> Result<()> {
+        let mut value = json!({
+            "game": "Dota 2",
+            "entries": [{"timestamp": "2024-01-01T20:00:00+01:00", "winner": ["a"], "loser": ["b"]}],
+        });
+        assert_eq!(schema_version(&value)?, 0);
+        migrate(&HISTORY, &mut value)?;
+        assert_eq!(schema_version(&value)?, HISTORY.current_version());
+        assert_eq!(value["entries"][0]["id"], "20240101-190000-000");
+
+        // Current files are left as they are
+        let migrated = value.clone();
+        migrate(&HISTORY, &mut value)?;
+        assert_eq!(value, migrated);
+        Ok(())
+    }
+
+    #[test]
+    fn newer_version_is_refused() {
+        let mut value = json!({"schema_version": STATE.current_version() + 1});
+        assert!(migrate(&STATE, &mut value).is_err());
+        assert!(schema_version(&json!({"schema_version": "1"})).is_err());
+    }
+
+    #[test]
+    fn versioned_keeps_fields() -> Result<()> {
+        #[derive(Serialize)]
+        struct Data {
+            a: i32,
+        }
+        let value = serde_json::to_value(Versioned::new(&PLAYERS, &Data { a: 1 }))?;
+        assert_eq!(value, json!({"schema_version": 1, "a": 1}));
+        Ok(())
+    }
+}
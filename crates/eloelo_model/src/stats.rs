@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::history::HistoryEntry;
+use crate::{PlayerId, WinScale};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WinLoss {
+    pub wins: u32,
+    pub loses: u32,
+}
+
+impl WinLoss {
+    pub fn matches(&self) -> u32 {
+        self.wins + self.loses
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        match self.matches() {
+            0 => 0.0,
+            matches => self.wins as f64 / matches as f64,
+        }
+    }
+
+    fn add(&mut self, won: bool) {
+        if won {
+            self.wins += 1;
+        } else {
+            self.loses += 1;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WinLossByScale {
+    pub even: WinLoss,
+    pub advantage: WinLoss,
+    pub pwnage: WinLoss,
+}
+
+impl WinLossByScale {
+    fn get_mut(&mut self, scale: WinScale) -> &mut WinLoss {
+        match scale {
+            WinScale::Even => &mut self.even,
+            WinScale::Advantage => &mut self.advantage,
+            WinScale::Pwnage => &mut self.pwnage,
+        }
+    }
+}
+
+/// Record of a player in matches with or against `player`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Relationship {
+    pub player: PlayerId,
+    pub record: WinLoss,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerStats {
+    pub player: PlayerId,
+    pub overall: WinLoss,
+    pub by_scale: WinLossByScale,
+    /// Sorted by win rate, best teammates first
+    pub teammates: Vec<Relationship>,
+    /// Sorted by win rate, so nemeses come last
+    pub opponents: Vec<Relationship>,
+}
+
+/// Statistics of every player in `history`, sorted by player. Fake matches are ignored.
+pub fn player_stats(history: &[HistoryEntry]) -> Vec<PlayerStats> {
+    #[derive(Default)]
+    struct Accumulator {
+        overall: WinLoss,
+        by_scale: WinLossByScale,
+        teammates: HashMap<PlayerId, WinLoss>,
+        opponents: HashMap<PlayerId, WinLoss>,
+    }
+
+    let mut stats: HashMap<&PlayerId, Accumulator> = HashMap::new();
+    for entry in history.iter().filter(|e| !e.fake) {
+        let sides = [
+            (&entry.winner, &entry.loser, true),
+            (&entry.loser, &entry.winner, false),
+        ];
+        for (team, other_team, won) in sides {
+            for player in team {
+                let acc = stats.entry(player).or_default();
+                acc.overall.add(won);
+                acc.by_scale.get_mut(entry.scale).add(won);
+                for teammate in team.iter().filter(|p| *p != player) {
+                    acc.teammates.entry(teammate.clone()).or_default().add(won);
+                }
+                for opponent in other_team {
+                    acc.opponents.entry(opponent.clone()).or_default().add(won);
+                }
+            }
+        }
+    }
+
+    let mut stats: Vec<_> = stats
+        .into_iter()
+        .map(|(player, acc)| PlayerStats {
+            player: player.clone(),
+            overall: acc.overall,
+            by_scale: acc.by_scale,
+            teammates: sorted_relationships(acc.teammates),
+            opponents: sorted_relationships(acc.opponents),
+        })
+        .collect();
+    stats.sort_by(|a, b| a.player.cmp(&b.player));
+    stats
+}
+
+fn sorted_relationships(records: HashMap<PlayerId, WinLoss>) -> Vec<Relationship> {
+    let mut relationships: Vec<_> = records
+        .into_iter()
+        .map(|(player, record)| Relationship { player, record })
+        .collect();
+    relationships.sort_by(|a, b| {
+        b.record
+            .win_rate()
+            .total_cmp(&a.record.win_rate())
+            .then(b.record.matches().cmp(&a.record.matches()))
+            .then(a.player.cmp(&b.player))
+    });
+    relationships
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(winner: &[&str], loser: &[&str], scale: WinScale, fake: bool) -> HistoryEntry {
+        HistoryEntry {
+            scale,
+            fake,
+            ..HistoryEntry::test_entry(0, winner, loser)
+        }
+    }
+
+    fn relationship(player: &str, wins: u32, loses: u32) -> Relationship {
+        Relationship {
+            player: PlayerId::from(player),
+            record: WinLoss { wins, loses },
+        }
+    }
+
+    #[test]
+    fn relationships_and_scales() {
+        let history = vec![
+            entry(&["a", "b"], &["c", "d"], WinScale::Even, false),
+            entry(&["a", "c"], &["b", "d"], WinScale::Pwnage, false),
+            entry(&["b", "d"], &["a", "c"], WinScale::Even, false),
+            entry(&["d"], &["a"], WinScale::Pwnage, true),
+        ];
+        let stats = player_stats(&history);
+        assert_eq!(stats.len(), 4);
+        let a = &stats[0];
+        assert_eq!(a.player, PlayerId::from("a"));
+        assert_eq!(a.overall, WinLoss { wins: 2, loses: 1 });
+        assert_eq!(a.by_scale.even, WinLoss { wins: 1, loses: 1 });
+        assert_eq!(a.by_scale.pwnage, WinLoss { wins: 1, loses: 0 });
+        assert_eq!(
+            a.teammates,
+            vec![relationship("b", 1, 0), relationship("c", 1, 1)]
+        );
+        assert_eq!(
+            a.opponents,
+            vec![
+                relationship("c", 1, 0),
+                relationship("d", 2, 1),
+                relationship("b", 1, 1),
+            ]
+        );
+    }
+}
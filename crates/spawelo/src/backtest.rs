@@ -0,0 +1,204 @@
+use std::time::Instant;
+
+use eloelo_model::history::HistoryEntry;
+use eloelo_model::player::{Player, PlayerWithElo};
+use eloelo_model::PlayerId;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::options::{RatingModelKind, SpaweloOptions};
+use crate::rating_model::{new_rating_model, RatingModel};
+use crate::training::training_window;
+
+const CALIBRATION_BINS: usize = 5;
+// Keeps log loss finite for overconfident predictions.
+const MIN_PROBABILITY: f64 = 1e-15;
+
+/// Quality of walk-forward predictions, see [`backtest`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BacktestReport {
+    /// Number of predicted matches
+    pub matches: usize,
+    /// Fraction of matches won by the predicted favourite (coin flips count as half)
+    pub accuracy: f64,
+    pub brier_score: f64,
+    pub log_loss: f64,
+    pub calibration: Vec<CalibrationBin>,
+}
+
+/// Favourite win rate for predictions within `[min_probability, max_probability)`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalibrationBin {
+    pub min_probability: f64,
+    pub max_probability: f64,
+    pub matches: usize,
+    pub mean_predicted: f64,
+    pub observed: f64,
+}
+
+/// Replays `history` chronologically, predicting each match with a model fitted only on the
+/// matches before it.
+///
+/// The first `warmup` matches are used only for training. `max_elo_history` from the ML ELO
+/// options limits the training window the same way it does for live ratings.
+pub fn backtest(
+    history: &[HistoryEntry],
+    options: &SpaweloOptions,
+    kind: RatingModelKind,
+    warmup: usize,
+) -> BacktestReport {
+    let start = Instant::now();
+    let window = training_window(options);
+    let mut model = new_rating_model(kind);
+    let predictions: Vec<f64> = (warmup.max(1)..history.len())
+        .filter(|&i| !history[i].fake)
+        .map(|i| {
+            let training = &history[i.saturating_sub(window)..i];
+            model.fit(training, options);
+            winner_probability(model.as_ref(), &history[i])
+        })
+        .collect();
+    info!(
+        "Backtest of {} matches took {:?}",
+        predictions.len(),
+        start.elapsed()
+    );
+    BacktestReport::from_predictions(&predictions)
+}
+
+fn winner_probability(model: &dyn RatingModel, entry: &HistoryEntry) -> f64 {
+    let ratings = model.ratings();
+    let with_elo = |players: &[PlayerId]| -> Vec<PlayerWithElo> {
+        players
+            .iter()
+            .map(|p| PlayerWithElo {
+                id: p.clone(),
+                elo: ratings
+                    .get(p)
+                    .map_or(Player::default_elo(), |elo| elo.round() as i32),
+            })
+            .collect()
+    };
+    model.win_probability(
+        &with_elo(&entry.winner),
+        &with_elo(&entry.loser),
+        entry.winner_side,
+    )
+}
+
+impl BacktestReport {
+    /// Builds the report from probabilities predicted for the actual winners.
+    pub fn from_predictions(predictions: &[f64]) -> Self {
+        if predictions.is_empty() {
+            return Default::default();
+        }
+        let n = predictions.len() as f64;
+        let accuracy = predictions
+            .iter()
+            .map(|&p| match p {
+                p if p > 0.5 => 1.0,
+                p if p < 0.5 => 0.0,
+                _ => 0.5,
+            })
+            .sum::<f64>()
+            / n;
+        let brier_score = predictions.iter().map(|p| (1.0 - p).powi(2)).sum::<f64>() / n;
+        let log_loss = predictions
+            .iter()
+            .map(|p| -p.max(MIN_PROBABILITY).ln())
+            .sum::<f64>()
+            / n;
+
+        BacktestReport {
+            matches: predictions.len(),
+            accuracy,
+            brier_score,
+            log_loss,
+            calibration: calibration(predictions),
+        }
+    }
+}
+
+// Predictions are folded to the favourite's perspective, which ranges from 0.5 to 1.0.
+fn calibration(predictions: &[f64]) -> Vec<CalibrationBin> {
+    let width = 0.5 / CALIBRATION_BINS as f64;
+    let mut bins: Vec<CalibrationBin> = (0..CALIBRATION_BINS)
+        .map(|i| CalibrationBin {
+            min_probability: 0.5 + i as f64 * width,
+            max_probability: 0.5 + (i + 1) as f64 * width,
+            ..Default::default()
+        })
+        .collect();
+    for &p in predictions {
+        let (favourite, favourite_won) = if p >= 0.5 { (p, 1.0) } else { (1.0 - p, 0.0) };
+        let i = (((favourite - 0.5) / width) as usize).min(CALIBRATION_BINS - 1);
+        let bin = &mut bins[i];
+        bin.matches += 1;
+        bin.mean_predicted += favourite;
+        bin.observed += favourite_won;
+    }
+    for bin in bins.iter_mut().filter(|b| b.matches > 0) {
+        bin.mean_predicted /= bin.matches as f64;
+        bin.observed /= bin.matches as f64;
+    }
+    bins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_entry(day: i64, winner: [&str; 2], loser: [&str; 2], fake: bool) -> HistoryEntry {
+        HistoryEntry {
+            fake,
+            ..HistoryEntry::test_entry(day * 24 * 60 * 60, winner, loser)
+        }
+    }
+
+    #[test]
+    fn strong_player_is_predicted() {
+        let lineups = [
+            (["a", "b"], ["c", "d"]),
+            (["a", "c"], ["b", "d"]),
+            (["a", "d"], ["b", "c"]),
+        ];
+        let history: Vec<_> = (0..30)
+            .map(|day| {
+                let (winner, loser) = lineups[day as usize % 3];
+                make_entry(day, winner, loser, day == 20)
+            })
+            .collect();
+        for kind in [RatingModelKind::MlElo, RatingModelKind::TrueSkill] {
+            let report = backtest(&history, &SpaweloOptions::default(), kind, 5);
+            // Fake match is used for training only
+            assert_eq!(report.matches, 24);
+            assert!(report.accuracy > 0.9, "{kind}: {}", report.accuracy);
+            assert!(report.log_loss < 2.0f64.ln(), "{kind}: {}", report.log_loss);
+        }
+    }
+
+    #[test]
+    fn report_from_predictions() {
+        let report = BacktestReport::from_predictions(&[0.85, 0.65, 0.25, 0.5]);
+        assert_eq!(report.matches, 4);
+        assert!((report.accuracy - 0.625).abs() < 1e-9);
+        let brier = (0.0225 + 0.1225 + 0.5625 + 0.25) / 4.0;
+        assert!((report.brier_score - brier).abs() < 1e-9);
+        let log_loss = -(0.85f64.ln() + 0.65f64.ln() + 0.25f64.ln() + 0.5f64.ln()) / 4.0;
+        assert!((report.log_loss - log_loss).abs() < 1e-9);
+
+        let matches: Vec<_> = report.calibration.iter().map(|b| b.matches).collect();
+        assert_eq!(matches, vec![1, 1, 1, 1, 0]);
+        // 0.25 for the winner means the favourite (0.75) lost
+        assert_eq!(report.calibration[2].observed, 0.0);
+        assert!((report.calibration[2].mean_predicted - 0.75).abs() < 1e-9);
+        assert_eq!(report.calibration[3].observed, 1.0);
+    }
+
+    #[test]
+    fn empty_report() {
+        assert_eq!(BacktestReport::from_predictions(&[]), Default::default());
+    }
+}
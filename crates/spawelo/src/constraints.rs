@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use eloelo_model::player::PlayerWithElo;
+use eloelo_model::{PlayerId, Team};
+use itertools::Itertools as _;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::options::ConstraintsOptions;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ShuffleError {
+    #[error("{0} and {1} are required to play both together and apart")]
+    Contradiction(PlayerId, PlayerId),
+    #[error("{0} and {1} must play together but are pinned to different teams")]
+    ConflictingPins(PlayerId, PlayerId),
+    #[error("No team split satisfies the team constraints")]
+    Unsatisfiable,
+}
+
+#[derive(Error, Debug)]
+#[error("Invalid constraint: {0}")]
+pub struct ConstraintParseError(String);
+
+/// Pairs of players written as `alice+bob, carol+dave`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PlayerPairs(pub Vec<(PlayerId, PlayerId)>);
+
+impl FromStr for PlayerPairs {
+    type Err = ConstraintParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        entries(s)
+            .map(|entry| match entry.split_once('+') {
+                Some((a, b)) if !a.trim().is_empty() && !b.trim().is_empty() => {
+                    Ok((PlayerId::from(a.trim()), PlayerId::from(b.trim())))
+                }
+                _ => Err(ConstraintParseError(entry.to_string())),
+            })
+            .collect::<Result<_, _>>()
+            .map(PlayerPairs)
+    }
+}
+
+impl Display for PlayerPairs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pairs: Vec<_> = self.0.iter().map(|(a, b)| format!("{a}+{b}")).collect();
+        f.write_str(&pairs.join(", "))
+    }
+}
+
+/// Players pinned to a team, written as `alice=left, bob=right`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PlayerPins(pub Vec<(PlayerId, Team)>);
+
+impl FromStr for PlayerPins {
+    type Err = ConstraintParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        entries(s)
+            .map(|entry| {
+                entry
+                    .split_once('=')
+                    .filter(|(player, _)| !player.trim().is_empty())
+                    .and_then(|(player, team)| {
+                        Some((PlayerId::from(player.trim()), Team::from_str(team.trim())?))
+                    })
+                    .ok_or_else(|| ConstraintParseError(entry.to_string()))
+            })
+            .collect::<Result<_, _>>()
+            .map(PlayerPins)
+    }
+}
+
+impl Display for PlayerPins {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pins: Vec<_> = self
+            .0
+            .iter()
+            .map(|(player, team)| match team {
+                Team::Left => format!("{player}=left"),
+                Team::Right => format!("{player}=right"),
+            })
+            .collect();
+        f.write_str(&pins.join(", "))
+    }
+}
+
+fn entries(s: &str) -> impl Iterator<Item = &str> {
+    s.split(',').map(str::trim).filter(|e| !e.is_empty())
+}
+
+macro_rules! string_conversions {
+    ($type:ty) => {
+        impl TryFrom<String> for $type {
+            type Error = ConstraintParseError;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                value.parse()
+            }
+        }
+
+        impl From<$type> for String {
+            fn from(value: $type) -> Self {
+                value.to_string()
+            }
+        }
+    };
+}
+
+string_conversions!(PlayerPairs);
+string_conversions!(PlayerPins);
+
+/// Constraints resolved to indices of the players being shuffled. Constraints mentioning
+/// players outside of the lobby are ignored.
+#[derive(Debug, Default)]
+pub(crate) struct Constraints {
+    /// `Some(true)` for players pinned to the left team
+    pins: Vec<Option<bool>>,
+    together: Vec<Vec<usize>>,
+    apart: Vec<Vec<usize>>,
+    /// Groups of players that always play together
+    groups: Vec<Vec<usize>>,
+}
+
+impl Constraints {
+    pub fn new(
+        players: &[PlayerWithElo],
+        options: &ConstraintsOptions,
+    ) -> Result<Self, ShuffleError> {
+        let n = players.len();
+        let index: HashMap<&PlayerId, usize> = players
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (&p.id, i))
+            .collect();
+        let resolve = |pairs: &PlayerPairs| -> Vec<(usize, usize)> {
+            pairs
+                .0
+                .iter()
+                .filter_map(|(a, b)| Some((*index.get(a)?, *index.get(b)?)))
+                .filter(|(a, b)| a != b)
+                .collect()
+        };
+
+        let mut constraints = Constraints {
+            pins: vec![None; n],
+            together: vec![Vec::new(); n],
+            apart: vec![Vec::new(); n],
+            groups: Vec::new(),
+        };
+        for (player, team) in &options.pinned.0 {
+            if let Some(&i) = index.get(player) {
+                constraints.pins[i] = Some(matches!(team, Team::Left));
+            }
+        }
+        let mut group_of: Vec<usize> = (0..n).collect();
+        for (a, b) in resolve(&options.always_together) {
+            constraints.together[a].push(b);
+            constraints.together[b].push(a);
+            let (from, to) = (group_of[b], group_of[a]);
+            group_of
+                .iter_mut()
+                .filter(|g| **g == from)
+                .for_each(|g| *g = to);
+        }
+        for (a, b) in resolve(&options.never_together) {
+            if group_of[a] == group_of[b] {
+                return Err(ShuffleError::Contradiction(
+                    players[a].id.clone(),
+                    players[b].id.clone(),
+                ));
+            }
+            constraints.apart[a].push(b);
+            constraints.apart[b].push(a);
+        }
+        constraints.groups = (0..n)
+            .into_group_map_by(|&i| group_of[i])
+            .into_values()
+            .sorted()
+            .collect();
+        for group in &constraints.groups {
+            let pinned: Vec<_> = group
+                .iter()
+                .filter_map(|&i| constraints.pins[i].map(|left| (i, left)))
+                .collect();
+            if let Some(&(a, left)) = pinned.first() {
+                if let Some(&(b, _)) = pinned.iter().find(|(_, l)| *l != left) {
+                    return Err(ShuffleError::ConflictingPins(
+                        players[a].id.clone(),
+                        players[b].id.clone(),
+                    ));
+                }
+            }
+        }
+        Ok(constraints)
+    }
+
+    pub fn has_pins(&self) -> bool {
+        self.pins.iter().any(Option::is_some)
+    }
+
+    pub fn groups(&self) -> &[Vec<usize>] {
+        &self.groups
+    }
+
+    /// Checks whether `player` may join the given side, considering only players assigned so far.
+    pub fn allows(&self, player: usize, left: bool, assigned: &[Option<bool>]) -> bool {
+        self.pins[player].is_none_or(|pin| pin == left)
+            && self.together[player]
+                .iter()
+                .all(|&other| assigned[other].is_none_or(|side| side == left))
+            && self.apart[player]
+                .iter()
+                .all(|&other| assigned[other].is_none_or(|side| side != left))
+    }
+
+    pub fn satisfied(&self, split: &[bool]) -> bool {
+        let assigned: Vec<_> = split.iter().copied().map(Some).collect();
+        (0..split.len()).all(|i| self.allows(i, split[i], &assigned))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn players(names: &[&str]) -> Vec<PlayerWithElo> {
+        names
+            .iter()
+            .map(|&name| PlayerWithElo {
+                id: PlayerId::from(name),
+                elo: 1000,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let pairs: PlayerPairs = " alice+bob,carol + dave ,".parse().unwrap();
+        assert_eq!(pairs.to_string(), "alice+bob, carol+dave");
+        let pins: PlayerPins = "alice=Left, bob=right".parse().unwrap();
+        assert_eq!(pins.to_string(), "alice=left, bob=right");
+        assert!("alice".parse::<PlayerPairs>().is_err());
+        assert!("alice=center".parse::<PlayerPins>().is_err());
+        assert_eq!("".parse::<PlayerPairs>().unwrap(), PlayerPairs::default());
+    }
+
+    #[test]
+    fn contradictions_are_reported() {
+        let players = players(&["a", "b", "c", "d"]);
+        let options = ConstraintsOptions {
+            always_together: "a+b, b+c".parse().unwrap(),
+            never_together: "a+c".parse().unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Constraints::new(&players, &options).unwrap_err(),
+            ShuffleError::Contradiction(PlayerId::from("a"), PlayerId::from("c"))
+        );
+
+        let options = ConstraintsOptions {
+            always_together: "a+b".parse().unwrap(),
+            pinned: "a=left, b=right, x=left".parse().unwrap(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Constraints::new(&players, &options),
+            Err(ShuffleError::ConflictingPins(_, _))
+        ));
+    }
+
+    #[test]
+    fn satisfied() {
+        let players = players(&["a", "b", "c", "d"]);
+        let options = ConstraintsOptions {
+            always_together: "a+b".parse().unwrap(),
+            never_together: "c+d, a+outsider".parse().unwrap(),
+            pinned: "c=right".parse().unwrap(),
+        };
+        let constraints = Constraints::new(&players, &options).unwrap();
+        assert_eq!(constraints.groups().len(), 3);
+        assert!(!constraints.satisfied(&[true, true, false, false]));
+        assert!(constraints.satisfied(&[true, true, false, true]));
+        assert!(!constraints.satisfied(&[false, false, true, false]));
+        assert!(!constraints.satisfied(&[true, false, false, true]));
+    }
+}
@@ -1,60 +1,147 @@
 use std::borrow::Borrow;
 use std::collections::HashMap;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use eloelo_model::history::HistoryEntry;
 use eloelo_model::player::{Player, PlayerWithElo};
-use eloelo_model::{BalancedTeam, PlayerId, WinScale};
+use eloelo_model::{BalancedTeam, PlayerId, Team, WinScale};
 
 use itertools::Itertools;
 use log::{debug, info};
-use rand::prelude::*;
+use serde::Serialize;
 
+mod backtest;
+mod constraints;
 mod options;
+mod rating_history;
+mod rating_model;
+mod shuffle;
+mod simulation;
+mod training;
+mod trueskill;
+mod tuner;
 
-pub use options::{MlEloOptions, PityBonusOptions, SpaweloOptions};
+pub use backtest::{backtest, BacktestReport, CalibrationBin};
+pub use constraints::{ConstraintParseError, PlayerPairs, PlayerPins, ShuffleError};
+pub use options::{
+    ConstraintsOptions, DurationMode, MlEloOptions, PityBonusOptions, PityMode, RatingModelKind,
+    RatingModelOptions, ShuffleOptions, SpaweloOptions, TrueSkillOptions,
+};
+pub use rating_history::{rating_history, PlayerRatingHistory, RatingPoint};
+pub use rating_model::{new_rating_model, MlElo, RatingModel};
+pub use shuffle::{shuffle_teams, TeamSplit};
+pub use simulation::{
+    simulate, RatingErrorPoint, SimulationError, SimulationParams, SimulationReport,
+};
+pub use training::{
+    fake_match_deadline, training_history, training_window, without_outdated_fakes,
+};
+pub use trueskill::{SkillEstimate, TrueSkill};
+pub use tuner::{tune_ml_elo, TuningResult};
 
 // Learning rate is set to very high level to make the computation faster. Learning is not really 100% finished after 1000 iterations, but it gives good results, and blazing fast with this setting
 const LEARNING_RATE: f64 = 5000.0;
 const ML_ITERATIONS: usize = 5_000;
-const WEIGHT_DECAY: f64 = 0.001;
+// Loss is checked every LOSS_CHECK_INTERVAL iterations, fitting stops when it improves by less
+// than LOSS_TOLERANCE relative. The high learning rate makes the loss oscillate, so a rise doesn't
+// stop the fitting. When the best loss isn't beaten for LOSS_PATIENCE checks, the loss isn't going
+// to settle and fitting stops with the best ratings.
+const LOSS_CHECK_INTERVAL: usize = 10;
+const LOSS_TOLERANCE: f64 = 1e-6;
+const LOSS_PATIENCE: usize = 20;
 
-fn print_debug(
-    i: usize,
-    history: &[HistoryEntry],
-    elo: &HashMap<PlayerId, f64>,
-    elo_sum: f64,
-    options: &MlEloOptions,
-) {
-    let loss = loss(history, &elo, options);
-    if i % 1000 == 0 || i == ML_ITERATIONS - 1 {
-        debug!(
-            "{}/{}, loss: {:.4}, elo_sum: {}",
-            i + 1,
-            ML_ITERATIONS,
-            loss,
-            elo_sum
-        );
+/// Outcome of [`ml_elo_warm_start`].
+#[derive(Debug, Clone, Default)]
+pub struct MlEloFit {
+    pub ratings: HashMap<PlayerId, f64>,
+    /// Fitted only when [`MlEloOptions::pair_synergy`] is enabled
+    pub synergies: Vec<PairSynergy>,
+    /// Elo bonus of the left side, fitted only when [`MlEloOptions::side_advantage`] is enabled
+    /// and history has matches with recorded sides
+    pub side_advantage: Option<f64>,
+    pub iterations: usize,
+    pub loss: f64,
+}
+
+/// Elo bonus of a pair of players when they play in the same team.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairSynergy {
+    pub players: (PlayerId, PlayerId),
+    pub synergy: f64,
+    /// Number of matches played together
+    pub matches: usize,
+}
+
+/// Key of a pair independent of players order.
+pub fn pair_key(a: &PlayerId, b: &PlayerId) -> (PlayerId, PlayerId) {
+    if a <= b {
+        (a.clone(), b.clone())
+    } else {
+        (b.clone(), a.clone())
     }
 }
 
 pub fn ml_elo(history: &[HistoryEntry], options: &MlEloOptions) -> HashMap<PlayerId, f64> {
-    let mut elo: HashMap<PlayerId, f64> = history
-        .iter()
-        .flat_map(|e| e.all_players())
-        .map(|p| (p.clone(), Player::default_elo() as f64))
-        .collect();
+    ml_elo_warm_start(history, options, &MlEloFit::default()).ratings
+}
 
-    let games_per_player = history.iter().fold(HashMap::new(), |mut acc, entry| {
-        for p in entry.all_players() {
-            *acc.entry(p.clone()).or_insert(0.0f64) += 1.0f64;
-        }
-        acc
-    });
+/// Fits elo starting from `previous` ratings and synergies (new players start at default elo).
+///
+/// Stops early once the loss stops improving, so refitting after a single new match with
+/// previous ratings as a starting point takes only a few iterations.
+pub fn ml_elo_warm_start(
+    history: &[HistoryEntry],
+    options: &MlEloOptions,
+    previous: &MlEloFit,
+) -> MlEloFit {
+    fit_ml_elo(history, options, previous, true)
+}
 
-    if elo.is_empty() {
+fn fit_ml_elo(
+    history: &[HistoryEntry],
+    options: &MlEloOptions,
+    previous: &MlEloFit,
+    stop_early: bool,
+) -> MlEloFit {
+    let dense = DenseHistory::new(history, options);
+    if dense.players.is_empty() {
         return Default::default();
     }
+    let previous_synergies: HashMap<_, _> = previous
+        .synergies
+        .iter()
+        .map(|s| (&s.players, s.synergy))
+        .collect();
+    let player_count = dense.players.len();
+    // Player ratings followed by pair synergies and side advantage
+    let mut elo: Vec<f64> = dense
+        .players
+        .iter()
+        .map(|p| {
+            previous
+                .ratings
+                .get(p)
+                .copied()
+                .unwrap_or(Player::default_elo() as f64)
+        })
+        .chain(
+            dense
+                .pairs
+                .iter()
+                .map(|pair| previous_synergies.get(pair).copied().unwrap_or(0.0)),
+        )
+        .chain(
+            dense
+                .side_index
+                .map(|_| previous.side_advantage.unwrap_or(0.0)),
+        )
+        .collect();
+    let participation: Vec<f64> = dense
+        .games_per_player
+        .iter()
+        .map(|games| games / (history.len() as f64))
+        .collect();
 
     info!(
         "Calculating ELO from {} historic matches. Max iterations: {}",
@@ -62,34 +149,82 @@ pub fn ml_elo(history: &[HistoryEntry], options: &MlEloOptions) -> HashMap<Playe
         ML_ITERATIONS
     );
 
+    let weight_decay = options.weight_decay.as_f64();
+    let synergy_regularization = options.synergy_regularization.as_f64();
+
     let start: Instant = Instant::now();
-    for i in 0..ML_ITERATIONS {
-        let elo_sum: f64 = elo.values().sum();
-        print_debug(i, history, &elo, elo_sum, options);
+    let mut last_loss = dense.loss(&elo);
+    let mut best = (last_loss, elo.clone());
+    let mut checks_since_best = 0;
+    let mut iterations = 0;
+    while iterations < ML_ITERATIONS {
+        let elo_sum: f64 = elo[..player_count].iter().sum();
 
-        let derivative: HashMap<PlayerId, f64> = backpropagation(history, &elo, options);
-        for (player, diff) in derivative {
-            *elo.entry(player).or_default() += diff * LEARNING_RATE;
+        let derivative = dense.backpropagation(&elo);
+        for (value, diff) in elo.iter_mut().zip(derivative) {
+            *value += diff * LEARNING_RATE;
         }
 
         // weight decay
-        let avg_elo = if elo.len() > 0 {
-            elo_sum / (elo.len() as f64)
-        } else {
-            1000.0
-        };
-        for (player, value) in elo.iter_mut() {
-            let player_participation_factor =
-                games_per_player.get(player).unwrap_or(&0.0f64) / (history.len() as f64);
-            *value -= (*value - avg_elo) * (WEIGHT_DECAY * player_participation_factor);
+        let avg_elo = elo_sum / (player_count as f64);
+        for (value, player_participation_factor) in elo.iter_mut().zip(&participation) {
+            *value -= (*value - avg_elo) * (weight_decay * player_participation_factor);
+        }
+        for synergy in &mut elo[player_count..player_count + dense.pairs.len()] {
+            *synergy -= *synergy * synergy_regularization;
+        }
+        iterations += 1;
+
+        if iterations % LOSS_CHECK_INTERVAL == 0 {
+            let loss = dense.loss(&elo);
+            debug!(
+                "{}/{}, loss: {:.6}, elo_sum: {}",
+                iterations, ML_ITERATIONS, loss, elo_sum
+            );
+            let converged = (0.0..last_loss * LOSS_TOLERANCE).contains(&(last_loss - loss));
+            last_loss = loss;
+            if loss < best.0 {
+                best = (loss, elo.clone());
+                checks_since_best = 0;
+            } else {
+                checks_since_best += 1;
+            }
+            if stop_early && converged {
+                break;
+            }
+            if stop_early && checks_since_best >= LOSS_PATIENCE {
+                elo = best.1;
+                break;
+            }
         }
     }
+    let final_loss = dense.loss(&elo);
 
-    // log_elo(&elo);
-    // log_probabilities(&elo, history);
-    info!("ELO calculations took {:?}", start.elapsed());
+    info!(
+        "ELO calculations took {:?}, iterations: {}, loss: {:.6}",
+        start.elapsed(),
+        iterations,
+        final_loss
+    );
 
-    elo
+    let synergies = dense
+        .pairs
+        .iter()
+        .zip(&dense.games_per_pair)
+        .zip(&elo[player_count..])
+        .map(|((players, &matches), &synergy)| PairSynergy {
+            players: players.clone(),
+            synergy,
+            matches,
+        })
+        .collect();
+    MlEloFit {
+        side_advantage: dense.side_index.map(|i| elo[i]),
+        ratings: dense.players.iter().cloned().zip(elo).collect(),
+        synergies,
+        iterations,
+        loss: final_loss,
+    }
 }
 
 #[allow(dead_code)]
@@ -110,7 +245,7 @@ fn log_probabilities(
         let loser_elo: f64 = entry.loser.iter().map(|p| elo.get(p).unwrap()).sum();
 
         let predicted_probability = win_probability(winner_elo, loser_elo);
-        let real_probability = advantage_factor(entry.scale, options);
+        let real_probability = target_probability(entry, options);
         debug!(
             "Winner: {}, Loser: {}, Real probability: {:.4}, Predicted probability: {:.4}",
             winner_elo, loser_elo, real_probability, predicted_probability,
@@ -118,20 +253,23 @@ fn log_probabilities(
     }
 }
 
-// L2 loss
-fn loss(history: &[HistoryEntry], elo: &HashMap<PlayerId, f64>, options: &MlEloOptions) -> f64 {
-    let mut loss = 0.0;
-    for entry in history {
-        let winner_elo: f64 = entry.winner.iter().map(|p| elo.get(p).unwrap()).sum();
-        let loser_elo: f64 = entry.loser.iter().map(|p| elo.get(p).unwrap()).sum();
-
-        let computed_probability = win_probability(winner_elo, loser_elo);
-        let real_probablity = advantage_factor(entry.scale, options);
-
-        loss += (real_probablity - computed_probability).powf(2.0);
-    }
-
-    loss
+/// Weight of each history entry, halved every `time_decay_half_life_days` counting back from the
+/// most recent match.
+fn time_decay_weights(history: &[HistoryEntry], options: &MlEloOptions) -> Vec<f64> {
+    let half_life_days = options.time_decay_half_life_days;
+    let Some(latest) = history.iter().map(|e| e.timestamp).max() else {
+        return Vec::new();
+    };
+    history
+        .iter()
+        .map(|entry| {
+            if half_life_days <= 0 {
+                return 1.0;
+            }
+            let age_days = (latest - entry.timestamp).num_seconds() as f64 / (24.0 * 60.0 * 60.0);
+            0.5f64.powf(age_days / half_life_days as f64)
+        })
+        .collect()
 }
 
 fn advantage_factor(scale: WinScale, options: &MlEloOptions) -> f64 {
@@ -142,23 +280,214 @@ fn advantage_factor(scale: WinScale, options: &MlEloOptions) -> f64 {
     }
 }
 
-fn backpropagation(
+/// Short matches are decisive: target probability falls linearly from pwnage target for
+/// `short_match_minutes` to even target for `long_match_minutes`. None if duration is unknown.
+fn duration_factor(duration: Duration, options: &MlEloOptions) -> Option<f64> {
+    if duration.is_zero() {
+        return None;
+    }
+    let minutes = duration.as_secs_f64() / 60.0;
+    let short = options.short_match_minutes as f64;
+    let long = (options.long_match_minutes as f64).max(short + 1.0);
+    let decisiveness = ((long - minutes) / (long - short)).clamp(0.0, 1.0);
+    let even = options.even_match_target_probability.as_f64();
+    let pwnage = options.pwnage_match_target_probability.as_f64();
+    Some(even + (pwnage - even) * decisiveness)
+}
+
+fn target_probability(entry: &HistoryEntry, options: &MlEloOptions) -> f64 {
+    let scale = advantage_factor(entry.scale, options);
+    let Some(duration) = duration_factor(entry.duration, options) else {
+        return scale;
+    };
+    match options.duration_mode {
+        DurationMode::Ignore => scale,
+        DurationMode::Replace => duration,
+        DurationMode::Blend => {
+            let weight = options.duration_blend_weight.as_f64().clamp(0.0, 1.0);
+            (1.0 - weight) * scale + weight * duration
+        }
+    }
+}
+
+// Above this many matches the gradient is computed on multiple threads.
+const PARALLEL_MIN_ENTRIES: usize = 2_000;
+
+/// History prepared for fitting: players mapped to dense indices, target probability and
+/// time-decay weight precomputed for every match.
+///
+/// With pair synergy enabled, indices of teammate pairs follow player indices. Side advantage
+/// comes last and is added to the team that played on the left side.
+struct DenseHistory {
+    players: Vec<PlayerId>,
+    games_per_player: Vec<f64>,
+    pairs: Vec<(PlayerId, PlayerId)>,
+    games_per_pair: Vec<usize>,
+    side_index: Option<usize>,
+    entries: Vec<DenseEntry>,
+}
+
+struct DenseEntry {
+    winner: Vec<usize>,
+    loser: Vec<usize>,
+    target_probability: f64,
+    weight: f64,
+}
+
+impl DenseEntry {
+    fn team_elo(team: &[usize], elo: &[f64]) -> f64 {
+        team.iter().map(|&p| elo[p]).sum()
+    }
+}
+
+impl DenseHistory {
+    fn new(history: &[HistoryEntry], options: &MlEloOptions) -> Self {
+        let mut players = Vec::new();
+        let mut games_per_player = Vec::new();
+        let mut index: HashMap<PlayerId, usize> = HashMap::new();
+        let mut to_indices = |team: &[PlayerId]| -> Vec<usize> {
+            team.iter()
+                .map(|player| {
+                    let i = match index.get(player) {
+                        Some(&i) => i,
+                        None => {
+                            index.insert(player.clone(), players.len());
+                            players.push(player.clone());
+                            games_per_player.push(0.0);
+                            players.len() - 1
+                        }
+                    };
+                    games_per_player[i] += 1.0;
+                    i
+                })
+                .collect()
+        };
+        let weights = time_decay_weights(history, options);
+        let mut entries: Vec<DenseEntry> = history
+            .iter()
+            .zip(weights)
+            .map(|(entry, weight)| DenseEntry {
+                winner: to_indices(&entry.winner),
+                loser: to_indices(&entry.loser),
+                target_probability: target_probability(entry, options),
+                weight,
+            })
+            .collect();
+
+        let (pairs, games_per_pair) = if options.pair_synergy {
+            frequent_pairs(history, options.min_pair_matches.max(1) as usize)
+        } else {
+            Default::default()
+        };
+        if !pairs.is_empty() {
+            let pair_index: HashMap<&(PlayerId, PlayerId), usize> = pairs
+                .iter()
+                .enumerate()
+                .map(|(i, pair)| (pair, players.len() + i))
+                .collect();
+            let team_pairs = |team: &[PlayerId]| -> Vec<usize> {
+                team.iter()
+                    .tuple_combinations()
+                    .filter_map(|(a, b)| pair_index.get(&pair_key(a, b)).copied())
+                    .collect()
+            };
+            for (dense, entry) in entries.iter_mut().zip(history) {
+                dense.winner.extend(team_pairs(&entry.winner));
+                dense.loser.extend(team_pairs(&entry.loser));
+            }
+        }
+
+        let has_sides = history.iter().any(|e| e.winner_side.is_some());
+        let side_index = (options.side_advantage && has_sides).then(|| players.len() + pairs.len());
+        if let Some(side_index) = side_index {
+            for (dense, entry) in entries.iter_mut().zip(history) {
+                match entry.winner_side {
+                    Some(Team::Left) => dense.winner.push(side_index),
+                    Some(Team::Right) => dense.loser.push(side_index),
+                    None => {}
+                }
+            }
+        }
+
+        Self {
+            players,
+            games_per_player,
+            pairs,
+            games_per_pair,
+            side_index,
+            entries,
+        }
+    }
+
+    // L2 loss
+    fn loss(&self, elo: &[f64]) -> f64 {
+        let mut loss = 0.0;
+        for entry in &self.entries {
+            let winner_elo = DenseEntry::team_elo(&entry.winner, elo);
+            let loser_elo = DenseEntry::team_elo(&entry.loser, elo);
+
+            let computed_probability = win_probability(winner_elo, loser_elo);
+            loss += entry.weight * (entry.target_probability - computed_probability).powf(2.0);
+        }
+
+        loss
+    }
+
+    fn backpropagation(&self, elo: &[f64]) -> Vec<f64> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        if self.entries.len() < PARALLEL_MIN_ENTRIES || threads < 2 {
+            return backpropagation(&self.entries, elo);
+        }
+
+        let chunk_size = self.entries.len().div_ceil(threads);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .entries
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || backpropagation(chunk, elo)))
+                .collect();
+            let mut derivative = vec![0.0; elo.len()];
+            for handle in handles {
+                let partial = handle.join().expect("gradient thread panicked");
+                for (total, d) in derivative.iter_mut().zip(partial) {
+                    *total += d;
+                }
+            }
+            derivative
+        })
+    }
+}
+
+/// Teammate pairs with at least `min_matches` matches together, sorted for determinism.
+fn frequent_pairs(
     history: &[HistoryEntry],
-    elo: &HashMap<PlayerId, f64>,
-    options: &MlEloOptions,
-) -> HashMap<PlayerId, f64> {
-    let mut derivative = HashMap::new();
-    for entry in history {
-        let winner_elo: f64 = entry.winner.iter().map(|p| elo.get(p).unwrap()).sum();
-        let loser_elo: f64 = entry.loser.iter().map(|p| elo.get(p).unwrap()).sum();
+    min_matches: usize,
+) -> (Vec<(PlayerId, PlayerId)>, Vec<usize>) {
+    history
+        .iter()
+        .flat_map(|e| [&e.winner, &e.loser])
+        .flat_map(|team| team.iter().tuple_combinations())
+        .map(|(a, b)| pair_key(a, b))
+        .counts()
+        .into_iter()
+        .filter(|(_, count)| *count >= min_matches)
+        .sorted()
+        .unzip()
+}
+
+fn backpropagation(entries: &[DenseEntry], elo: &[f64]) -> Vec<f64> {
+    let mut derivative = vec![0.0; elo.len()];
+    for entry in entries {
+        let winner_elo = DenseEntry::team_elo(&entry.winner, elo);
+        let loser_elo = DenseEntry::team_elo(&entry.loser, elo);
         let elo_diff = winner_elo - loser_elo;
 
         let computed_probability = win_probability(winner_elo, loser_elo);
-        let real_probability = advantage_factor(entry.scale, options);
 
         // ((x-c)^2)' = 2*(x-c)
         // L2 loss
-        let final_derivative = 2.0 * (real_probability - computed_probability);
+        let final_derivative =
+            2.0 * entry.weight * (entry.target_probability - computed_probability);
 
         // https://www.wolframalpha.com/input?i=%281%2F%281%2B10%5E%28-x%2F400%29%29%29%27
         // -log(10)/(400 (1 + 10^(x/400))^2) + log(10)/(400 (1 + 10^(x/400)))
@@ -166,11 +495,11 @@ fn backpropagation(
             * (-10.0f64.ln() / (400.0 * (1.0 + 10.0f64.powf(elo_diff / 400.0)).powf(2.0))
                 + 10.0f64.ln() / (400.0 * (1.0 + 10.0f64.powf(elo_diff / 400.0))));
 
-        for p in &entry.winner {
-            *derivative.entry(p.clone()).or_insert(0.0) += win_probability_derivative;
+        for &p in &entry.winner {
+            derivative[p] += win_probability_derivative;
         }
-        for p in &entry.loser {
-            *derivative.entry(p.clone()).or_insert(0.0) -= win_probability_derivative;
+        for &p in &entry.loser {
+            derivative[p] -= win_probability_derivative;
         }
     }
 
@@ -182,72 +511,68 @@ fn win_probability(winner_elo: f64, loser_elo: f64) -> f64 {
     1.0 / (1.0 + 10.0f64.powf(-elo_diff / 400.0))
 }
 
-pub fn shuffle_teams(
-    players: impl IntoIterator<Item = PlayerWithElo>,
+fn player_lose_streaks<'a>(
+    team: &'a [impl Borrow<PlayerWithElo>],
     lose_streaks: &HashMap<PlayerId, i32>,
-    temperature: i32,
-    options: &SpaweloOptions,
-) -> (BalancedTeam, BalancedTeam) {
-    let players: Vec<_> = players.into_iter().collect();
-    let team_size = players.len() / 2;
-
-    struct BestChoice<'a> {
-        diff: i32,
-        teams: (Vec<&'a PlayerId>, Vec<&'a PlayerId>),
-        info: (TeamEloInfo, TeamEloInfo),
-    }
-    let mut best_choice = BestChoice {
-        diff: players.iter().map(|p| p.elo).sum(),
-        teams: Default::default(),
-        info: Default::default(),
-    };
+) -> Vec<(&'a PlayerWithElo, i32)> {
+    team.iter()
+        .map(|p| {
+            let p = p.borrow();
+            (p, lose_streaks.get(&p.id).copied().unwrap_or(0))
+        })
+        .collect()
+}
 
-    let mut rng = rand::rng();
+/// Applies pity bonus to `info.real_elo` according to `options.pity_bonus.mode`.
+fn apply_team_pity_bonus(
+    info: &mut TeamEloInfo,
+    team: &[impl Borrow<PlayerWithElo>],
+    lose_streaks: &HashMap<PlayerId, i32>,
+    options: &SpaweloOptions,
+) {
+    let streaks = player_lose_streaks(team, lose_streaks);
+    let min_loses = options.pity_bonus.min_loses.max(1);
+    let max_streak = streaks.iter().map(|(_, s)| *s).max().unwrap_or(0);
+    let sum_streak: i32 = streaks.iter().map(|(_, s)| (*s).max(0)).sum();
 
-    let mut apply_temperature = |elo: i32| {
-        if temperature > 0 {
-            elo + rng.random_range(0..temperature)
-        } else {
-            elo
+    if options.pity_bonus.mode == PityMode::PerPlayer {
+        let mut mul_elo = 0;
+        for (player, streak) in &streaks {
+            let (_, add, elo) = apply_pity_bonus(player.elo, *streak, options);
+            if *streak >= min_loses {
+                info.pity_contributors.push(player.id.clone());
+            }
+            mul_elo += elo - add - player.elo;
+            info.pity_bonus_add += add;
         }
-    };
-
-    for team in players.iter().combinations(team_size) {
-        let other_team: Vec<_> = players.iter().filter(|p| !team.contains(&p)).collect();
-        let (team_info, other_info) =
-            calculate_teams_elo_internal(&team, &other_team, lose_streaks, options);
-
-        let diff =
-            (apply_temperature(team_info.pity_elo) - apply_temperature(other_info.pity_elo)).abs();
-        if diff < best_choice.diff {
-            best_choice.diff = diff;
-            best_choice.teams.0 = team.iter().map(|p| &p.id).collect();
-            best_choice.info.0 = team_info;
-            best_choice.teams.1 = other_team.iter().map(|p| &p.id).collect();
-            best_choice.info.1 = other_info;
+        let plain_elo = calculate_team_real_elo(team);
+        if plain_elo != 0 {
+            info.pity_bonus_mul = mul_elo as f64 / plain_elo as f64;
         }
+        info.lose_streak = max_streak;
+        info.pity_elo = info.real_elo + mul_elo + info.pity_bonus_add;
+        return;
     }
 
-    (
-        build_balanced_team(
-            best_choice.teams.0.into_iter().cloned().collect(),
-            best_choice.info.0,
-        ),
-        build_balanced_team(
-            best_choice.teams.1.into_iter().cloned().collect(),
-            best_choice.info.1,
-        ),
-    )
-}
-
-fn max_lose_streak_for_team(
-    team: &[impl Borrow<PlayerWithElo>],
-    lose_streaks: &HashMap<PlayerId, i32>,
-) -> i32 {
-    team.into_iter()
-        .map(|p| lose_streaks.get(&p.borrow().id).copied().unwrap_or(0))
-        .max()
-        .unwrap_or(0)
+    info.lose_streak = match options.pity_bonus.mode {
+        PityMode::SumStreaks => sum_streak,
+        PityMode::AverageStreak if !streaks.is_empty() => {
+            (sum_streak as f64 / streaks.len() as f64).round() as i32
+        }
+        _ => max_streak,
+    };
+    (info.pity_bonus_mul, info.pity_bonus_add, info.pity_elo) =
+        apply_pity_bonus(info.real_elo, info.lose_streak, options);
+    if info.lose_streak >= min_loses {
+        info.pity_contributors = streaks
+            .iter()
+            .filter(|(_, streak)| match options.pity_bonus.mode {
+                PityMode::MaxStreak => *streak == max_streak,
+                _ => *streak > 0,
+            })
+            .map(|(p, _)| p.id.clone())
+            .collect();
+    }
 }
 
 fn apply_pity_bonus(
@@ -281,9 +606,10 @@ pub fn calculate_teams_elo(
     right_players: Vec<PlayerWithElo>,
     lose_streaks: &HashMap<PlayerId, i32>,
     options: &SpaweloOptions,
+    model: &dyn RatingModel,
 ) -> (BalancedTeam, BalancedTeam) {
     let (left, right) =
-        calculate_teams_elo_internal(&left_players, &right_players, lose_streaks, options);
+        calculate_teams_elo_internal(&left_players, &right_players, lose_streaks, options, model);
 
     (
         build_balanced_team(left_players, left),
@@ -298,17 +624,19 @@ fn build_balanced_team(players: Vec<impl Into<PlayerId>>, info: TeamEloInfo) ->
         pity_bonus_add: info.pity_bonus_add,
         pity_elo: info.pity_elo,
         real_elo: info.real_elo,
+        pity_contributors: info.pity_contributors,
     };
     players
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Clone, Default)]
 struct TeamEloInfo {
     pity_bonus_mul: f64,
     pity_bonus_add: i32,
     pity_elo: i32,
     real_elo: i32,
     lose_streak: i32,
+    pity_contributors: Vec<PlayerId>,
 }
 
 fn calculate_teams_elo_internal(
@@ -316,34 +644,38 @@ fn calculate_teams_elo_internal(
     right_players: &[impl Borrow<PlayerWithElo>],
     lose_streaks: &HashMap<PlayerId, i32>,
     options: &SpaweloOptions,
+    model: &dyn RatingModel,
 ) -> (TeamEloInfo, TeamEloInfo) {
-    let mut l = TeamEloInfo::default();
-    let mut r = TeamEloInfo::default();
-    l.lose_streak = max_lose_streak_for_team(&left_players, lose_streaks);
-    r.lose_streak = max_lose_streak_for_team(&right_players, lose_streaks);
-
-    l.real_elo = calculate_team_real_elo(&left_players);
-    r.real_elo = calculate_team_real_elo(&right_players);
-
-    (l.pity_bonus_mul, l.pity_bonus_add, l.pity_elo) =
-        apply_pity_bonus(l.real_elo, l.lose_streak, options);
-    (r.pity_bonus_mul, r.pity_bonus_add, r.pity_elo) =
-        apply_pity_bonus(r.real_elo, r.lose_streak, options);
+    let mut l = TeamEloInfo {
+        real_elo: model.team_elo(&owned_players(left_players)),
+        ..Default::default()
+    };
+    let mut r = TeamEloInfo {
+        real_elo: model.team_elo(&owned_players(right_players)),
+        ..Default::default()
+    };
+    apply_team_pity_bonus(&mut l, left_players, lose_streaks, options);
+    apply_team_pity_bonus(&mut r, right_players, lose_streaks, options);
     (l, r)
 }
 
+fn owned_players(players: &[impl Borrow<PlayerWithElo>]) -> Vec<PlayerWithElo> {
+    players.iter().map(|p| p.borrow().clone()).collect()
+}
+
 pub fn calculate_team_real_elo(left_players: &[impl Borrow<PlayerWithElo>]) -> i32 {
     left_players.into_iter().map(|p| p.borrow().elo).sum()
 }
 
-/// Calculates win chance for lhs
-pub fn calculate_win_prediction(lhs_elo: i32, rhs_elo: i32) -> f64 {
-    win_probability(lhs_elo.into(), rhs_elo.into())
+/// Calculates win chance for lhs. `side_advantage` is the elo bonus of the side lhs plays on.
+pub fn calculate_win_prediction(lhs_elo: i32, rhs_elo: i32, side_advantage: f64) -> f64 {
+    win_probability(lhs_elo as f64 + side_advantage, rhs_elo.into())
 }
 
 #[cfg(test)]
 mod test {
     use eloelo_model::decimal::Decimal;
+    use rand::prelude::*;
 
     use crate::options::PityBonusOptions;
 
@@ -378,10 +710,11 @@ mod test {
                 additive: false,
                 ..Default::default()
             },
-            ml_elo: Default::default(),
+            ..Default::default()
         };
         let lose_streaks = HashMap::from([(id("j"), 1)]);
-        let (t1, t2) = calculate_teams_elo_internal(&left, &right, &lose_streaks, &options);
+        let (t1, t2) =
+            calculate_teams_elo_internal(&left, &right, &lose_streaks, &options, &MlElo::default());
         assert_eq!(t1.real_elo, 1000);
         assert_eq!(t2.real_elo, 3000);
         assert_eq!(t1.pity_bonus_mul, -0.5);
@@ -402,10 +735,11 @@ mod test {
                 additive: false,
                 ..Default::default()
             },
-            ml_elo: Default::default(),
+            ..Default::default()
         };
         let lose_streaks = HashMap::from([(id("j"), 3)]);
-        let (t1, t2) = calculate_teams_elo_internal(&left, &right, &lose_streaks, &options);
+        let (t1, t2) =
+            calculate_teams_elo_internal(&left, &right, &lose_streaks, &options, &MlElo::default());
         assert_eq!(t1.real_elo, 1000);
         assert_eq!(t2.real_elo, 3000);
         assert_eq!(t1.pity_bonus_mul, -0.875);
@@ -430,10 +764,11 @@ mod test {
                 additive: false,
                 ..Default::default()
             },
-            ml_elo: Default::default(),
+            ..Default::default()
         };
         let lose_streaks = HashMap::from([(id("j"), 3)]);
-        let (t1, t2) = calculate_teams_elo_internal(&left, &right, &lose_streaks, &options);
+        let (t1, t2) =
+            calculate_teams_elo_internal(&left, &right, &lose_streaks, &options, &MlElo::default());
         assert_eq!(t1.real_elo, 1000);
         assert_eq!(t2.real_elo, 3000);
         assert_eq!(t1.pity_bonus_mul, -0.75);
@@ -455,10 +790,11 @@ mod test {
                 additive: false,
                 ..Default::default()
             },
-            ml_elo: Default::default(),
+            ..Default::default()
         };
         let lose_streaks = HashMap::from([(id("j"), 2)]);
-        let (t1, t2) = calculate_teams_elo_internal(&left, &right, &lose_streaks, &options);
+        let (t1, t2) =
+            calculate_teams_elo_internal(&left, &right, &lose_streaks, &options, &MlElo::default());
         assert_eq!(t1.real_elo, 1000);
         assert_eq!(t2.real_elo, 3000);
         assert_eq!(t1.pity_bonus_mul, -0.5);
@@ -480,10 +816,11 @@ mod test {
                 additive: false,
                 ..Default::default()
             },
-            ml_elo: Default::default(),
+            ..Default::default()
         };
         let lose_streaks = HashMap::from([(id("j"), 1)]);
-        let (t1, t2) = calculate_teams_elo_internal(&left, &right, &lose_streaks, &options);
+        let (t1, t2) =
+            calculate_teams_elo_internal(&left, &right, &lose_streaks, &options, &MlElo::default());
         assert_eq!(t1.real_elo, 1000);
         assert_eq!(t2.real_elo, 3000);
         assert_eq!(t1.pity_bonus_mul, -0.5);
@@ -492,15 +829,428 @@ mod test {
         assert_eq!(t2.pity_elo, 3000);
     }
 
+    fn pity_mode_options(mode: PityMode) -> SpaweloOptions {
+        SpaweloOptions {
+            pity_bonus: PityBonusOptions {
+                mode,
+                min_loses: 2,
+                additive: true,
+                additive_amount: 100,
+                multiplicative: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn three_players() -> Vec<PlayerWithElo> {
+        vec![player("a", 1000), player("b", 2000), player("c", 1000)]
+    }
+
+    #[test]
+    fn test_calculate_teams_elo_max_streak_contributors() {
+        let right = vec![player("d", 4000)];
+        let options = pity_mode_options(PityMode::MaxStreak);
+        let lose_streaks = HashMap::from([(id("a"), 3), (id("b"), 3), (id("c"), 1)]);
+        let (t1, t2) = calculate_teams_elo_internal(
+            &three_players(),
+            &right,
+            &lose_streaks,
+            &options,
+            &MlElo::default(),
+        );
+        assert_eq!(t1.pity_bonus_add, 200);
+        assert_eq!(t1.pity_elo, 4200);
+        assert_eq!(t1.pity_contributors, vec![id("a"), id("b")]);
+        assert_eq!(t2.pity_elo, 4000);
+        assert!(t2.pity_contributors.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_teams_elo_sum_streaks() {
+        let right = vec![player("d", 4000)];
+        let options = pity_mode_options(PityMode::SumStreaks);
+        // No player has a streak long enough on their own
+        let lose_streaks = HashMap::from([(id("a"), 1), (id("b"), 1), (id("c"), 1)]);
+        let (t1, _) = calculate_teams_elo_internal(
+            &three_players(),
+            &right,
+            &lose_streaks,
+            &options,
+            &MlElo::default(),
+        );
+        assert_eq!(t1.lose_streak, 3);
+        assert_eq!(t1.pity_bonus_add, 200);
+        assert_eq!(t1.pity_elo, 4200);
+        assert_eq!(t1.pity_contributors, vec![id("a"), id("b"), id("c")]);
+
+        let lose_streaks = HashMap::from([(id("a"), 1)]);
+        let (t1, _) = calculate_teams_elo_internal(
+            &three_players(),
+            &right,
+            &lose_streaks,
+            &options,
+            &MlElo::default(),
+        );
+        assert_eq!(t1.pity_elo, 4000);
+        assert!(t1.pity_contributors.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_teams_elo_average_streak() {
+        let right = vec![player("d", 4000)];
+        let options = pity_mode_options(PityMode::AverageStreak);
+        // 5 / 3 rounds to 2
+        let lose_streaks = HashMap::from([(id("a"), 4), (id("b"), 1)]);
+        let (t1, _) = calculate_teams_elo_internal(
+            &three_players(),
+            &right,
+            &lose_streaks,
+            &options,
+            &MlElo::default(),
+        );
+        assert_eq!(t1.lose_streak, 2);
+        assert_eq!(t1.pity_bonus_add, 100);
+        assert_eq!(t1.pity_elo, 4100);
+        assert_eq!(t1.pity_contributors, vec![id("a"), id("b")]);
+    }
+
+    #[test]
+    fn test_calculate_teams_elo_per_player() {
+        let right = vec![player("d", 4000)];
+        let options = SpaweloOptions {
+            pity_bonus: PityBonusOptions {
+                multiplicative: true,
+                factor: Decimal::new("-0.5"),
+                ..pity_mode_options(PityMode::PerPlayer).pity_bonus
+            },
+            ..Default::default()
+        };
+        let lose_streaks = HashMap::from([(id("a"), 3), (id("b"), 2), (id("c"), 1)]);
+        let (t1, t2) = calculate_teams_elo_internal(
+            &three_players(),
+            &right,
+            &lose_streaks,
+            &options,
+            &MlElo::default(),
+        );
+        // a: 1000 * 0.25 + 200, b: 2000 * 0.5 + 100, c: no bonus
+        assert_eq!(t1.real_elo, 4000);
+        assert_eq!(t1.pity_bonus_add, 300);
+        assert_eq!(t1.pity_bonus_mul, -0.4375);
+        assert_eq!(t1.pity_elo, 2550);
+        assert_eq!(t1.pity_contributors, vec![id("a"), id("b")]);
+        assert_eq!(t2.pity_elo, 4000);
+    }
+
     #[test]
     fn win_prediction() {
         assert_eq!(
-            Decimal::with_precision(calculate_win_prediction(1000, 1000), 2),
+            Decimal::with_precision(calculate_win_prediction(1000, 1000, 0.0), 2),
             Decimal::new("0.5")
         );
         assert_eq!(
-            Decimal::with_precision(calculate_win_prediction(1100, 1000), 2),
+            Decimal::with_precision(calculate_win_prediction(1100, 1000, 0.0), 2),
             Decimal::new("0.64")
         );
+        assert_eq!(
+            Decimal::with_precision(calculate_win_prediction(1000, 1000, 100.0), 2),
+            Decimal::new("0.64")
+        );
+    }
+
+    fn entry_on_day(day: i64) -> HistoryEntry {
+        HistoryEntry::test_entry(day * 24 * 60 * 60, ["a"], ["b"])
+    }
+
+    #[test]
+    fn time_decay_weights_halve_every_half_life() {
+        let history = [entry_on_day(0), entry_on_day(30), entry_on_day(60)];
+        let options = MlEloOptions {
+            time_decay_half_life_days: 30,
+            ..Default::default()
+        };
+        let weights = time_decay_weights(&history, &options);
+        assert_eq!(weights.len(), 3);
+        assert!((weights[0] - 0.25).abs() < 1e-9);
+        assert!((weights[1] - 0.5).abs() < 1e-9);
+        assert!((weights[2] - 1.0).abs() < 1e-9);
+
+        let disabled = time_decay_weights(&history, &MlEloOptions::default());
+        assert_eq!(disabled, vec![1.0; 3]);
+    }
+
+    fn synthetic_history(matches: i64) -> Vec<HistoryEntry> {
+        let players = ["a", "b", "c", "d", "e", "f"];
+        (0..matches)
+            .map(|i| {
+                let mut lineup: Vec<_> = players.iter().map(|p| id(p)).collect();
+                lineup.rotate_left((i % 6) as usize);
+                let (left, right) = lineup.split_at(3);
+                // "a" is the strongest player, though their team loses from time to time.
+                let (winner, loser) = if left.contains(&id("a")) != (i % 5 == 0) {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                HistoryEntry {
+                    winner: winner.to_vec(),
+                    loser: loser.to_vec(),
+                    scale: [WinScale::Even, WinScale::Advantage, WinScale::Pwnage]
+                        [(i % 3) as usize],
+                    ..entry_on_day(i)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn warm_start_converges_faster() {
+        let options = MlEloOptions::default();
+        let history = synthetic_history(40);
+        let cold = ml_elo_warm_start(&history[..39], &options, &MlEloFit::default());
+        let warm = ml_elo_warm_start(&history, &options, &cold);
+        let reference = ml_elo_warm_start(&history, &options, &MlEloFit::default());
+
+        assert!(warm.iterations < reference.iterations);
+        assert!(warm.loss <= reference.loss * 1.01);
+        for (player, elo) in &reference.ratings {
+            assert!((warm.ratings[player] - elo).abs() < 10.0, "{player}");
+        }
+    }
+
+    /// Random 5v5 matches among 12 players with hidden strengths.
+    fn random_history(seed: u64, matches: i64) -> Vec<HistoryEntry> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let players: Vec<_> = (0..12).map(|i| (id(&format!("p{i}")), i as f64)).collect();
+        (0..matches)
+            .map(|day| {
+                let mut lineup: Vec<_> = players.choose_multiple(&mut rng, 10).cloned().collect();
+                lineup.shuffle(&mut rng);
+                let (left, right) = lineup.split_at(5);
+                let strength = |team: &[(PlayerId, f64)]| team.iter().map(|p| p.1).sum::<f64>();
+                let diff = strength(left) - strength(right) + rng.random_range(-15.0..15.0);
+                let (winner, loser) = if diff > 0.0 {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                let scale = match diff.abs() {
+                    d if d > 12.0 => WinScale::Pwnage,
+                    d if d > 6.0 => WinScale::Advantage,
+                    _ => WinScale::Even,
+                };
+                HistoryEntry {
+                    winner: winner.iter().map(|p| p.0.clone()).collect(),
+                    loser: loser.iter().map(|p| p.0.clone()).collect(),
+                    scale,
+                    ..entry_on_day(day)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ml_elo_regression() {
+        // Computed with the HashMap based implementation preceding dense indices and early
+        // stopping, running all ML_ITERATIONS on the same history and options.
+        let cases = [
+            (
+                MlEloOptions::default(),
+                26.63332979191565,
+                [
+                    ("p0", 890.551160613668),
+                    ("p1", 716.1680039958851),
+                    ("p2", 850.4964675791721),
+                    ("p3", 882.8689167016543),
+                    ("p4", 954.4926281695706),
+                    ("p5", 959.1547436512185),
+                    ("p6", 981.1178678477995),
+                    ("p7", 1159.9991632080053),
+                    ("p8", 1026.256593851007),
+                    ("p9", 1063.464545549763),
+                    ("p10", 1190.404323333045),
+                    ("p11", 1294.770609381104),
+                ],
+            ),
+            (
+                MlEloOptions {
+                    time_decay_half_life_days: 90,
+                    ..Default::default()
+                },
+                6.132913449297821,
+                [
+                    ("p0", 881.7283973820038),
+                    ("p1", 876.199257219705),
+                    ("p2", 892.9491533531616),
+                    ("p3", 906.2516373548918),
+                    ("p4", 970.2690867177532),
+                    ("p5", 990.6438735745505),
+                    ("p6", 1005.2184809590324),
+                    ("p7", 1085.5434176541055),
+                    ("p8", 1052.491043242793),
+                    ("p9", 1083.7123733097544),
+                    ("p10", 1106.3990053665545),
+                    ("p11", 1124.0373836265887),
+                ],
+            ),
+        ];
+        let history = random_history(7, 300);
+        for (options, loss, expected) in cases {
+            let fit = fit_ml_elo(&history, &options, &MlEloFit::default(), false);
+            assert!((fit.loss - loss).abs() < 1e-6);
+            assert_eq!(fit.ratings.len(), expected.len());
+            for (player, elo) in expected {
+                assert!((fit.ratings[&id(player)] - elo).abs() < 1e-6, "{player}");
+            }
+        }
+    }
+
+    #[test]
+    fn early_stop_is_as_good_as_full_fit() {
+        let history = random_history(7, 300);
+        for options in [
+            MlEloOptions::default(),
+            MlEloOptions {
+                time_decay_half_life_days: 90,
+                ..Default::default()
+            },
+        ] {
+            let early = ml_elo_warm_start(&history, &options, &MlEloFit::default());
+            let full = fit_ml_elo(&history, &options, &MlEloFit::default(), false);
+            assert!(early.iterations < full.iterations);
+            assert!(early.loss <= full.loss * (1.0 + LOSS_TOLERANCE));
+            // Loss doesn't depend on the average rating, only its drift differs
+            let offset = early.ratings[&id("p0")] - full.ratings[&id("p0")];
+            for (player, elo) in &full.ratings {
+                assert!(
+                    (early.ratings[player] - elo - offset).abs() < 0.01,
+                    "{player}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_gradient_matches_sequential() {
+        let history = random_history(3, PARALLEL_MIN_ENTRIES as i64 + 500);
+        let dense = DenseHistory::new(&history, &MlEloOptions::default());
+        let elo: Vec<f64> = (0..dense.players.len())
+            .map(|i| 900.0 + 20.0 * i as f64)
+            .collect();
+        let sequential = backpropagation(&dense.entries, &elo);
+        let parallel = dense.backpropagation(&elo);
+        for (s, p) in sequential.iter().zip(&parallel) {
+            assert!((s - p).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn duration_target_probability() {
+        let entry = |minutes: u64| HistoryEntry {
+            scale: WinScale::Advantage,
+            duration: Duration::from_secs(minutes * 60),
+            ..entry_on_day(0)
+        };
+        let mut options = MlEloOptions::default();
+        assert_eq!(target_probability(&entry(20), &options), 0.85);
+
+        options.duration_mode = DurationMode::Replace;
+        assert!((target_probability(&entry(15), &options) - 0.95).abs() < 1e-9);
+        assert!((target_probability(&entry(45), &options) - 0.85).abs() < 1e-9);
+        assert!((target_probability(&entry(90), &options) - 0.75).abs() < 1e-9);
+        // Unknown duration falls back to scale
+        assert_eq!(target_probability(&entry(0), &options), 0.85);
+
+        options.duration_mode = DurationMode::Blend;
+        assert!((target_probability(&entry(20), &options) - 0.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pair_synergy() {
+        // "a" and "b" win whenever they play together, and lose otherwise.
+        let lineups = [
+            (["a", "b"], ["c", "d"]),
+            (["c", "d"], ["a", "c"]),
+            (["a", "b"], ["c", "d"]),
+            (["c", "d"], ["b", "d"]),
+            (["a", "b"], ["c", "d"]),
+            (["a", "d"], ["b", "c"]),
+            (["b", "c"], ["a", "d"]),
+        ];
+        let history: Vec<_> = (0..70)
+            .map(|day| {
+                let (winner, loser) = lineups[day as usize % lineups.len()];
+                HistoryEntry {
+                    winner: winner.iter().map(|p| id(p)).collect(),
+                    loser: loser.iter().map(|p| id(p)).collect(),
+                    ..entry_on_day(day)
+                }
+            })
+            .collect();
+        let options = MlEloOptions {
+            pair_synergy: true,
+            ..Default::default()
+        };
+        let fit = ml_elo_warm_start(&history, &options, &MlEloFit::default());
+        let strongest = fit
+            .synergies
+            .iter()
+            .max_by(|a, b| a.synergy.total_cmp(&b.synergy))
+            .unwrap();
+        assert_eq!(strongest.players, (id("a"), id("b")));
+        assert_eq!(strongest.matches, 30);
+        assert!(strongest.synergy > 0.0);
+
+        let without = ml_elo_warm_start(&history, &MlEloOptions::default(), &MlEloFit::default());
+        assert!(without.synergies.is_empty());
+        assert!(fit.loss < without.loss);
+    }
+
+    #[test]
+    fn side_advantage() {
+        // Every lineup is played on both sides, left side wins three times out of four.
+        let lineups = [(["a", "b"], ["c", "d"]), (["a", "c"], ["b", "d"])];
+        let history: Vec<_> = (0..80)
+            .map(|day| {
+                let (left, right) = lineups[day as usize / 2 % 2];
+                let (left, right) = if day % 2 == 0 {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                let left_won = day % 4 != 3;
+                let (winner, loser) = if left_won {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                HistoryEntry {
+                    winner: winner.iter().map(|p| id(p)).collect(),
+                    loser: loser.iter().map(|p| id(p)).collect(),
+                    winner_side: Some(if left_won { Team::Left } else { Team::Right }),
+                    ..entry_on_day(day)
+                }
+            })
+            .collect();
+        let options = MlEloOptions {
+            side_advantage: true,
+            ..Default::default()
+        };
+        let fit = ml_elo_warm_start(&history, &options, &MlEloFit::default());
+        assert!(fit.side_advantage.unwrap() > 50.0);
+        let without = ml_elo_warm_start(&history, &MlEloOptions::default(), &MlEloFit::default());
+        assert_eq!(without.side_advantage, None);
+        assert!(fit.loss < without.loss);
+
+        let no_sides: Vec<_> = history
+            .into_iter()
+            .map(|e| HistoryEntry {
+                winner_side: None,
+                ..e
+            })
+            .collect();
+        let fit = ml_elo_warm_start(&no_sides, &options, &MlEloFit::default());
+        assert_eq!(fit.side_advantage, None);
     }
 }
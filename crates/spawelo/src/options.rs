@@ -1,13 +1,20 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
 use eloelo_model::{
     decimal::Decimal,
     options::{DescribedOption, Options},
+    GameId,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::constraints::{PlayerPairs, PlayerPins};
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(default)]
 pub struct PityBonusOptions {
+    pub mode: PityMode,
     pub additive: bool,
     pub multiplicative: bool,
     pub factor: Decimal,
@@ -19,6 +26,7 @@ pub struct PityBonusOptions {
 impl Default for PityBonusOptions {
     fn default() -> Self {
         Self {
+            mode: PityMode::MaxStreak,
             additive: true,
             multiplicative: false,
             factor: Decimal::with_precision(-0.02, 2),
@@ -45,6 +53,11 @@ impl Options for PityBonusOptions {
                 "loseStreakMaxDays",
                 "Lose Streak Max Age [Days]",
             ),
+            DescribedOption::with_text(
+                self.mode,
+                "mode",
+                "Mode (maxStreak, sumStreaks, averageStreak, perPlayer)",
+            ),
             DescribedOption::with_int(self.min_loses, "minLoses", "Min Loses"),
             DescribedOption::with_bool(self.additive, "additive", "Additive"),
             DescribedOption::with_int(self.additive_amount, "additiveAmount", "Additive Amount"),
@@ -54,15 +67,86 @@ impl Options for PityBonusOptions {
     }
 }
 
+/// Which lose streaks of a team give it the pity bonus.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PityMode {
+    /// Longest streak in the team
+    #[default]
+    MaxStreak,
+    /// Sum of streaks of all team members
+    SumStreaks,
+    /// Average streak, rounded to whole loses
+    AverageStreak,
+    /// Elo of every player is adjusted by their own streak before summing
+    PerPlayer,
+}
+
+impl Display for PityMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Must match serde representation, UI sends it back as text.
+        f.write_str(match self {
+            PityMode::MaxStreak => "maxStreak",
+            PityMode::SumStreaks => "sumStreaks",
+            PityMode::AverageStreak => "averageStreak",
+            PityMode::PerPlayer => "perPlayer",
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(default)]
 pub struct MlEloOptions {
     pub fake_match_max_days: i32,
     pub max_elo_history: i32,
+    /// Age after which match weight drops by half, measured from the most recent match.
+    /// Zero disables time decay.
+    pub time_decay_half_life_days: i32,
     pub even_match_target_probability: Decimal,
     pub advantage_match_target_probability: Decimal,
     pub pwnage_match_target_probability: Decimal,
+    /// Strength of pulling ratings towards the average, scaled by player participation.
+    pub weight_decay: Decimal,
+    pub duration_mode: DurationMode,
+    /// Matches this short get pwnage target probability in duration based modes
+    pub short_match_minutes: i32,
+    /// Matches this long get even target probability in duration based modes
+    pub long_match_minutes: i32,
+    /// Share of the duration based probability in [`DurationMode::Blend`]
+    pub duration_blend_weight: Decimal,
+    /// Fit elo bonus for pairs of teammates
+    pub pair_synergy: bool,
+    /// Pull of pair synergy towards zero on every iteration
+    pub synergy_regularization: Decimal,
+    /// Pairs with fewer matches together get no synergy term
+    pub min_pair_matches: i32,
+    /// Fit elo bonus of the left side, from matches with recorded sides
+    pub side_advantage: bool,
+}
+
+/// How match duration affects the target win probability of a match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DurationMode {
+    /// Target probability depends only on win scale
+    #[default]
+    Ignore,
+    /// Target probability depends only on match duration
+    Replace,
+    /// Weighted average of scale and duration based probabilities
+    Blend,
+}
+
+impl Display for DurationMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Must match serde representation, UI sends it back as text.
+        f.write_str(match self {
+            DurationMode::Ignore => "ignore",
+            DurationMode::Replace => "replace",
+            DurationMode::Blend => "blend",
+        })
+    }
 }
 
 impl Default for MlEloOptions {
@@ -70,9 +154,19 @@ impl Default for MlEloOptions {
         Self {
             fake_match_max_days: 99999,
             max_elo_history: 0,
+            time_decay_half_life_days: 0,
             even_match_target_probability: Decimal::new("0.75"),
             advantage_match_target_probability: Decimal::new("0.85"),
             pwnage_match_target_probability: Decimal::new("0.95"),
+            weight_decay: Decimal::new("0.001"),
+            duration_mode: DurationMode::Ignore,
+            short_match_minutes: 20,
+            long_match_minutes: 70,
+            duration_blend_weight: Decimal::new("0.5"),
+            pair_synergy: false,
+            synergy_regularization: Decimal::new("0.01"),
+            min_pair_matches: 5,
+            side_advantage: false,
         }
     }
 }
@@ -98,6 +192,11 @@ impl Options for MlEloOptions {
                 "maxEloHistory",
                 "Max Elo History [Matches]",
             ),
+            DescribedOption::with_int(
+                self.time_decay_half_life_days,
+                "timeDecayHalfLifeDays",
+                "Time Decay Half-Life [Days] (0 = off)",
+            ),
             DescribedOption::with_decimal(
                 self.even_match_target_probability.clone(),
                 "evenMatchTargetProbability",
@@ -113,21 +212,282 @@ impl Options for MlEloOptions {
                 "pwnageMatchTargetProbability",
                 "Pwnage Match Target Probability",
             ),
+            DescribedOption::with_decimal(self.weight_decay.clone(), "weightDecay", "Weight Decay"),
+            DescribedOption::with_text(
+                self.duration_mode,
+                "durationMode",
+                "Duration Mode (ignore, replace, blend)",
+            ),
+            DescribedOption::with_int(
+                self.short_match_minutes,
+                "shortMatchMinutes",
+                "Short Match [Minutes]",
+            ),
+            DescribedOption::with_int(
+                self.long_match_minutes,
+                "longMatchMinutes",
+                "Long Match [Minutes]",
+            ),
+            DescribedOption::with_decimal(
+                self.duration_blend_weight.clone(),
+                "durationBlendWeight",
+                "Duration Blend Weight",
+            ),
+            DescribedOption::with_bool(self.pair_synergy, "pairSynergy", "Pair Synergy"),
+            DescribedOption::with_decimal(
+                self.synergy_regularization.clone(),
+                "synergyRegularization",
+                "Synergy Regularization",
+            ),
+            DescribedOption::with_int(
+                self.min_pair_matches,
+                "minPairMatches",
+                "Synergy Min Matches Together",
+            ),
+            DescribedOption::with_bool(self.side_advantage, "sideAdvantage", "Side Advantage"),
+        ]
+    }
+}
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RatingModelKind {
+    #[default]
+    MlElo,
+    TrueSkill,
+}
+
+impl Display for RatingModelKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Must match serde representation, UI sends it back as text.
+        f.write_str(match self {
+            RatingModelKind::MlElo => "mlElo",
+            RatingModelKind::TrueSkill => "trueSkill",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct TrueSkillOptions {
+    /// Deviation of a player without any matches.
+    pub initial_deviation: i32,
+    /// Deviation of a single match performance around player's rating.
+    pub performance_deviation: i32,
+    /// Deviation increase per day without playing.
+    pub deviation_growth_per_day: Decimal,
+    pub advantage_margin_factor: Decimal,
+    pub pwnage_margin_factor: Decimal,
+    /// Fit skill bonus of the left side, from matches with recorded sides
+    pub side_advantage: bool,
+}
+
+impl Default for TrueSkillOptions {
+    fn default() -> Self {
+        Self {
+            initial_deviation: 250,
+            performance_deviation: 125,
+            deviation_growth_per_day: Decimal::new("12"),
+            advantage_margin_factor: Decimal::new("1.25"),
+            pwnage_margin_factor: Decimal::new("1.5"),
+            side_advantage: false,
+        }
+    }
+}
+
+impl Options for TrueSkillOptions {
+    fn key() -> String {
+        "trueSkill".into()
+    }
+
+    fn name() -> String {
+        "TrueSkill Options".into()
+    }
+
+    fn to_described_options(&self) -> Vec<DescribedOption> {
+        vec![
+            DescribedOption::with_int(
+                self.initial_deviation,
+                "initialDeviation",
+                "Initial Deviation",
+            ),
+            DescribedOption::with_int(
+                self.performance_deviation,
+                "performanceDeviation",
+                "Performance Deviation",
+            ),
+            DescribedOption::with_decimal(
+                self.deviation_growth_per_day.clone(),
+                "deviationGrowthPerDay",
+                "Deviation Growth Per Day",
+            ),
+            DescribedOption::with_decimal(
+                self.advantage_margin_factor.clone(),
+                "advantageMarginFactor",
+                "Advantage Margin Factor",
+            ),
+            DescribedOption::with_decimal(
+                self.pwnage_margin_factor.clone(),
+                "pwnageMarginFactor",
+                "Pwnage Margin Factor",
+            ),
+            DescribedOption::with_bool(self.side_advantage, "sideAdvantage", "Side Advantage"),
+        ]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct ShuffleOptions {
+    /// Lobbies up to this size are searched exhaustively, larger ones heuristically.
+    pub exact_max_players: i32,
+    /// Number of random starting splits tried by the heuristic search.
+    pub heuristic_restarts: i32,
+    /// Number of best distinct splits offered after shuffling.
+    pub alternatives: i32,
+    /// Number of recent matches whose lineups are penalized (0 = off).
+    pub novelty_matches: i32,
+    /// Elo penalty for repeating a recent lineup exactly, scaled down by partial overlap.
+    pub novelty_weight: i32,
+}
+
+impl Default for ShuffleOptions {
+    fn default() -> Self {
+        Self {
+            exact_max_players: 20,
+            heuristic_restarts: 50,
+            alternatives: 5,
+            novelty_matches: 0,
+            novelty_weight: 100,
+        }
+    }
+}
+
+impl Options for ShuffleOptions {
+    fn key() -> String {
+        "shuffle".into()
+    }
+
+    fn name() -> String {
+        "Shuffle Options".into()
+    }
+
+    fn to_described_options(&self) -> Vec<DescribedOption> {
+        vec![
+            DescribedOption::with_int(
+                self.exact_max_players,
+                "exactMaxPlayers",
+                "Exact Search Max Players",
+            ),
+            DescribedOption::with_int(
+                self.heuristic_restarts,
+                "heuristicRestarts",
+                "Heuristic Search Restarts",
+            ),
+            DescribedOption::with_int(self.alternatives, "alternatives", "Alternative Splits"),
+            DescribedOption::with_int(
+                self.novelty_matches,
+                "noveltyMatches",
+                "Novelty: Recent Matches (0 = off)",
+            ),
+            DescribedOption::with_int(self.novelty_weight, "noveltyWeight", "Novelty: Weight"),
         ]
     }
 }
+
+/// Team composition rules applied when shuffling teams.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct ConstraintsOptions {
+    pub never_together: PlayerPairs,
+    pub always_together: PlayerPairs,
+    pub pinned: PlayerPins,
+}
+
+impl Options for ConstraintsOptions {
+    fn key() -> String {
+        "constraints".into()
+    }
+
+    fn name() -> String {
+        "Team Constraints".into()
+    }
+
+    fn to_described_options(&self) -> Vec<DescribedOption> {
+        vec![
+            DescribedOption::with_text(
+                &self.never_together,
+                "neverTogether",
+                "Never Together (a+b, c+d)",
+            ),
+            DescribedOption::with_text(
+                &self.always_together,
+                "alwaysTogether",
+                "Always Together (a+b, c+d)",
+            ),
+            DescribedOption::with_text(&self.pinned, "pinned", "Pinned (a=left, b=right)"),
+        ]
+    }
+}
+
+/// Rating model selected for each game. Games without an entry use the default model.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct RatingModelOptions {
+    pub games: HashMap<GameId, RatingModelKind>,
+}
+
+impl RatingModelOptions {
+    pub fn model_for(&self, game: &GameId) -> RatingModelKind {
+        self.games.get(game).copied().unwrap_or_default()
+    }
+
+    /// Makes sure every game has an entry, so it shows up in the options UI.
+    pub fn ensure_games<'a>(&mut self, games: impl IntoIterator<Item = &'a GameId>) {
+        for game in games {
+            self.games.entry(game.clone()).or_default();
+        }
+    }
+}
+
+impl Options for RatingModelOptions {
+    fn key() -> String {
+        "ratingModel".into()
+    }
+
+    fn name() -> String {
+        "Rating Model".into()
+    }
+
+    fn to_described_options(&self) -> Vec<DescribedOption> {
+        let mut games: Vec<_> = self.games.iter().collect();
+        games.sort_by_key(|(game, _)| *game);
+        games
+            .into_iter()
+            .map(|(game, kind)| DescribedOption::with_text(kind, game.as_str(), game.as_str()))
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(default)]
 pub struct SpaweloOptions {
     pub ml_elo: MlEloOptions,
     pub pity_bonus: PityBonusOptions,
+    pub rating_model: RatingModelOptions,
+    pub true_skill: TrueSkillOptions,
+    pub shuffle: ShuffleOptions,
+    pub constraints: ConstraintsOptions,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use anyhow::Result;
+    use eloelo_model::PlayerId;
 
     #[test]
     fn deserialize_pity_bonus_options() -> Result<()> {
@@ -160,4 +520,41 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn deserialize_rating_model_options() -> Result<()> {
+        let json_str = r#"{
+            "DotA 2": "trueSkill"
+        }"#;
+        let options = serde_json::from_str::<RatingModelOptions>(json_str)?;
+        assert_eq!(
+            options.model_for(&GameId::from("DotA 2")),
+            RatingModelKind::TrueSkill
+        );
+        assert_eq!(
+            options.model_for(&GameId::from("Other")),
+            RatingModelKind::MlElo
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_constraints_options() -> Result<()> {
+        let json_str = r#"{
+            "neverTogether": "alice+bob",
+            "pinned": "carol=right"
+        }"#;
+        let options = serde_json::from_str::<ConstraintsOptions>(json_str)?;
+        assert_eq!(
+            options.never_together.0,
+            vec![(PlayerId::from("alice"), PlayerId::from("bob"))]
+        );
+        assert!(options.always_together.0.is_empty());
+        assert_eq!(
+            serde_json::to_value(&options)?["pinned"],
+            serde_json::json!("carol=right")
+        );
+        assert!(serde_json::from_str::<ConstraintsOptions>(r#"{"pinned": "carol"}"#).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use chrono::{DateTime, Local};
+use eloelo_model::history::HistoryEntry;
+use eloelo_model::PlayerId;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::options::{RatingModelKind, SpaweloOptions};
+use crate::rating_model::new_rating_model;
+use crate::training::training_window;
+
+/// Rating of a player right after one of their matches.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingPoint {
+    pub timestamp: DateTime<Local>,
+    pub rating: i32,
+    /// Rating deviation, when the rating model tracks it
+    pub uncertainty: Option<i32>,
+    pub won: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerRatingHistory {
+    pub player: PlayerId,
+    pub points: Vec<RatingPoint>,
+}
+
+/// Ratings of players after each of their matches in `[from, to)`, sorted by player.
+///
+/// Replays chronologically ordered `history`, refitting the model after every match, so a point
+/// reflects only the matches played until then. Matches before `from` still count towards the
+/// ratings. Empty `players` selects everyone who played in the range.
+pub fn rating_history(
+    history: &[HistoryEntry],
+    options: &SpaweloOptions,
+    kind: RatingModelKind,
+    players: &[PlayerId],
+    from: Option<DateTime<Local>>,
+    to: Option<DateTime<Local>>,
+) -> Vec<PlayerRatingHistory> {
+    let start = Instant::now();
+    let window = training_window(options);
+    let in_range = |entry: &HistoryEntry| {
+        from.is_none_or(|from| entry.timestamp >= from) && to.is_none_or(|to| entry.timestamp < to)
+    };
+    let end = history
+        .iter()
+        .rposition(in_range)
+        .map_or(0, |last| last + 1);
+
+    let mut model = new_rating_model(kind);
+    let mut points: HashMap<PlayerId, Vec<RatingPoint>> = HashMap::new();
+    for (i, entry) in history[..end].iter().enumerate() {
+        if !in_range(entry) {
+            continue;
+        }
+        model.fit(&history[(i + 1).saturating_sub(window)..=i], options);
+        let ratings = model.ratings();
+        let teams = [(&entry.winner, true), (&entry.loser, false)];
+        for (player, won) in teams
+            .into_iter()
+            .flat_map(|(team, won)| team.iter().map(move |p| (p, won)))
+            .filter(|(p, _)| players.is_empty() || players.contains(p))
+        {
+            let Some(rating) = ratings.get(player) else {
+                continue;
+            };
+            points.entry(player.clone()).or_default().push(RatingPoint {
+                timestamp: entry.timestamp,
+                rating: rating.round() as i32,
+                uncertainty: model.uncertainty(player).map(|u| u.round() as i32),
+                won,
+            });
+        }
+    }
+    info!(
+        "Rating history of {} players took {:?}",
+        points.len(),
+        start.elapsed()
+    );
+
+    let mut result: Vec<_> = points
+        .into_iter()
+        .map(|(player, points)| PlayerRatingHistory { player, points })
+        .collect();
+    result.sort_by(|a, b| a.player.cmp(&b.player));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn entry(day: i64, winner: &str, loser: &str) -> HistoryEntry {
+        HistoryEntry::test_entry(day * 86400, [winner], [loser])
+    }
+
+    fn day(day: i64) -> Option<DateTime<Local>> {
+        Some(Utc.timestamp_opt(day * 86400, 0).unwrap().into())
+    }
+
+    #[test]
+    fn ratings_follow_results() {
+        let history: Vec<_> = (0..6).map(|d| entry(d, "a", "b")).collect();
+        let result = rating_history(
+            &history,
+            &SpaweloOptions::default(),
+            RatingModelKind::MlElo,
+            &[],
+            None,
+            None,
+        );
+        assert_eq!(result.len(), 2);
+        let a = &result[0];
+        assert_eq!(a.player, PlayerId::from("a"));
+        assert_eq!(a.points.len(), 6);
+        assert!(a.points.iter().all(|p| p.won));
+        assert!(a.points.last().unwrap().rating > result[1].points.last().unwrap().rating);
+    }
+
+    #[test]
+    fn date_range_and_player_filter() {
+        let history: Vec<_> = (0..6).map(|d| entry(d, "a", "b")).collect();
+        let full = rating_history(
+            &history,
+            &SpaweloOptions::default(),
+            RatingModelKind::MlElo,
+            &[PlayerId::from("b")],
+            None,
+            None,
+        );
+        let ranged = rating_history(
+            &history,
+            &SpaweloOptions::default(),
+            RatingModelKind::MlElo,
+            &[PlayerId::from("b")],
+            day(2),
+            day(4),
+        );
+        assert_eq!(ranged.len(), 1);
+        assert_eq!(ranged[0].player, PlayerId::from("b"));
+        // Earlier matches still count towards the ratings, up to convergence of the fit
+        assert_eq!(ranged[0].points.len(), 2);
+        for (ranged, full) in ranged[0].points.iter().zip(&full[0].points[2..4]) {
+            assert_eq!(ranged.timestamp, full.timestamp);
+            assert!((ranged.rating - full.rating).abs() <= 5);
+        }
+    }
+}
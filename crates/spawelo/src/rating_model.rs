@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use eloelo_model::history::HistoryEntry;
+use eloelo_model::player::PlayerWithElo;
+use eloelo_model::{PlayerId, Team};
+use itertools::Itertools;
+
+use crate::options::{RatingModelKind, SpaweloOptions};
+use crate::trueskill::TrueSkill;
+use crate::{
+    calculate_team_real_elo, calculate_win_prediction, ml_elo_warm_start, pair_key, MlEloFit,
+    PairSynergy,
+};
+
+/// Produces player ratings from match history and predicts match outcomes.
+///
+/// Ratings are expressed on the elo scale, so they can be stored as player elo and used for
+/// team balancing regardless of the model.
+pub trait RatingModel {
+    fn kind(&self) -> RatingModelKind;
+
+    /// Fits the model to chronologically ordered history.
+    fn fit(&mut self, history: &[HistoryEntry], options: &SpaweloOptions);
+
+    /// Ratings of all players known from the last fit.
+    fn ratings(&self) -> HashMap<PlayerId, f64>;
+
+    /// Standard deviation of the player's rating, for models that track it.
+    fn uncertainty(&self, _player: &PlayerId) -> Option<f64> {
+        None
+    }
+
+    /// Fitted teammate pair synergies, for models that support them.
+    fn synergies(&self) -> Vec<PairSynergy> {
+        Vec::new()
+    }
+
+    /// Fitted elo bonus of the left side, for models that support it.
+    fn side_advantage(&self) -> Option<f64> {
+        None
+    }
+
+    /// Strength of the team used for balancing.
+    fn team_elo(&self, team: &[PlayerWithElo]) -> i32 {
+        calculate_team_real_elo(team)
+    }
+
+    /// Calculates win chance for lhs. Side advantage applies only when `lhs_side` is known.
+    fn win_probability(
+        &self,
+        lhs: &[PlayerWithElo],
+        rhs: &[PlayerWithElo],
+        lhs_side: Option<Team>,
+    ) -> f64;
+}
+
+pub fn new_rating_model(kind: RatingModelKind) -> Box<dyn RatingModel + Send + Sync> {
+    match kind {
+        RatingModelKind::MlElo => Box::new(MlElo::default()),
+        RatingModelKind::TrueSkill => Box::new(TrueSkill::default()),
+    }
+}
+
+/// Default model: ratings fitted with [`crate::ml_elo`], logistic win probability on team elo sums.
+///
+/// Each fit is warm-started from the ratings of the previous one. Team elo includes pair
+/// synergies when they are enabled.
+#[derive(Debug, Clone, Default)]
+pub struct MlElo {
+    last_fit: MlEloFit,
+    synergies: HashMap<(PlayerId, PlayerId), f64>,
+}
+
+impl MlElo {
+    /// Result of the most recent fit, including iteration count and final loss.
+    pub fn last_fit(&self) -> &MlEloFit {
+        &self.last_fit
+    }
+}
+
+impl RatingModel for MlElo {
+    fn kind(&self) -> RatingModelKind {
+        RatingModelKind::MlElo
+    }
+
+    fn fit(&mut self, history: &[HistoryEntry], options: &SpaweloOptions) {
+        self.last_fit = ml_elo_warm_start(history, &options.ml_elo, &self.last_fit);
+        self.synergies = self
+            .last_fit
+            .synergies
+            .iter()
+            .map(|s| (s.players.clone(), s.synergy))
+            .collect();
+    }
+
+    fn ratings(&self) -> HashMap<PlayerId, f64> {
+        self.last_fit.ratings.clone()
+    }
+
+    fn synergies(&self) -> Vec<PairSynergy> {
+        self.last_fit.synergies.clone()
+    }
+
+    fn side_advantage(&self) -> Option<f64> {
+        self.last_fit.side_advantage
+    }
+
+    fn team_elo(&self, team: &[PlayerWithElo]) -> i32 {
+        let synergy: f64 = team
+            .iter()
+            .tuple_combinations()
+            .filter_map(|(a, b)| self.synergies.get(&pair_key(&a.id, &b.id)))
+            .sum();
+        calculate_team_real_elo(team) + synergy.round() as i32
+    }
+
+    fn win_probability(
+        &self,
+        lhs: &[PlayerWithElo],
+        rhs: &[PlayerWithElo],
+        lhs_side: Option<Team>,
+    ) -> f64 {
+        let advantage = self.last_fit.side_advantage.unwrap_or(0.0);
+        let side_advantage = match lhs_side {
+            Some(Team::Left) => advantage,
+            Some(Team::Right) => -advantage,
+            None => 0.0,
+        };
+        calculate_win_prediction(self.team_elo(lhs), self.team_elo(rhs), side_advantage)
+    }
+}
@@ -0,0 +1,721 @@
+use std::collections::HashMap;
+
+use eloelo_model::history::HistoryEntry;
+use eloelo_model::player::PlayerWithElo;
+use eloelo_model::{BalancedTeam, PlayerId, Team};
+use itertools::{Either, Itertools};
+use log::{debug, info};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::constraints::{Constraints, ShuffleError};
+use crate::options::{PityMode, SpaweloOptions};
+use crate::rating_model::RatingModel;
+use crate::{apply_pity_bonus, build_balanced_team, calculate_teams_elo_internal, TeamEloInfo};
+
+/// One of the team splits proposed by [`shuffle_teams`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamSplit {
+    pub left: BalancedTeam,
+    pub right: BalancedTeam,
+    /// Absolute difference of pity-adjusted team elo
+    pub elo_diff: i32,
+    /// Predicted win chance of the left team
+    pub win_chance: f64,
+    /// Penalty for resembling recent lineups, see `ShuffleOptions::novelty_weight`
+    pub novelty_penalty: i32,
+}
+
+/// Splits players into two teams with the smallest difference of pity-adjusted team elo.
+///
+/// Returns up to `shuffle.alternatives` distinct splits, best first. Lobbies up to
+/// `shuffle.exact_max_players` are searched exactly with branch and bound, larger ones with
+/// randomized local search. With an odd number of players both teams are allowed to get the
+/// extra player. Only splits satisfying `options.constraints` are considered.
+///
+/// Splits resembling the last `shuffle.novelty_matches` non-fake matches of `history` are
+/// penalized, so balanced but different lineups are preferred.
+///
+/// All randomness, i.e. temperature and local search, is drawn from `seed`, so the same inputs
+/// always give the same result.
+pub fn shuffle_teams(
+    players: impl IntoIterator<Item = PlayerWithElo>,
+    lose_streaks: &HashMap<PlayerId, i32>,
+    history: &[HistoryEntry],
+    temperature: i32,
+    seed: u64,
+    options: &SpaweloOptions,
+    model: &dyn RatingModel,
+) -> Result<Vec<TeamSplit>, ShuffleError> {
+    let players: Vec<_> = players.into_iter().collect();
+    if players.is_empty() {
+        return Ok(Vec::new());
+    }
+    if players.len() % 2 == 1 {
+        info!(
+            "Odd number of players ({}), teams will differ in size by one",
+            players.len()
+        );
+    }
+    let mut search = Search {
+        players: &players,
+        constraints: Constraints::new(&players, &options.constraints)?,
+        recent_lineups: recent_lineups(&players, history, options),
+        lose_streaks,
+        options,
+        model,
+        temperature,
+        rng: StdRng::seed_from_u64(seed),
+        best: Vec::new(),
+        alternatives: options.shuffle.alternatives.max(1) as usize,
+    };
+    if players.len() <= options.shuffle.exact_max_players.max(0) as usize {
+        search.branch_and_bound();
+    } else {
+        search.local_search(options.shuffle.heuristic_restarts.max(1) as usize);
+    }
+    if search.best.is_empty() {
+        return Err(ShuffleError::Unsatisfiable);
+    }
+
+    Ok(search
+        .best
+        .into_iter()
+        .map(|candidate| {
+            let (left, right): (Vec<_>, Vec<_>) = players
+                .iter()
+                .zip(&candidate.split)
+                .partition_map(|(p, &left)| {
+                    if left {
+                        Either::Left(p.clone())
+                    } else {
+                        Either::Right(p.clone())
+                    }
+                });
+            TeamSplit {
+                win_chance: model.win_probability(&left, &right, Some(Team::Left)),
+                elo_diff: (candidate.info.0.pity_elo - candidate.info.1.pity_elo).abs(),
+                novelty_penalty: candidate.novelty_penalty,
+                left: build_balanced_team(left, candidate.info.0),
+                right: build_balanced_team(right, candidate.info.1),
+            }
+        })
+        .collect())
+}
+
+struct Candidate {
+    /// `true` for players of the left team
+    split: Vec<bool>,
+    /// Elo difference including novelty penalty and temperature, minimized by the search
+    diff: i32,
+    novelty_penalty: i32,
+    info: (TeamEloInfo, TeamEloInfo),
+}
+
+struct Search<'a> {
+    players: &'a [PlayerWithElo],
+    constraints: Constraints,
+    /// Sides of players in recent matches, `Some(true)` for the winning team
+    recent_lineups: Vec<Vec<Option<bool>>>,
+    lose_streaks: &'a HashMap<PlayerId, i32>,
+    options: &'a SpaweloOptions,
+    model: &'a dyn RatingModel,
+    temperature: i32,
+    rng: StdRng,
+    /// Best distinct candidates found so far, sorted by `diff`
+    best: Vec<Candidate>,
+    alternatives: usize,
+}
+
+impl Search<'_> {
+    fn left_sizes(&self) -> Vec<usize> {
+        let n = self.players.len();
+        [n / 2, n - n / 2].into_iter().dedup().collect()
+    }
+
+    fn apply_temperature(&mut self, elo: i32) -> i32 {
+        if self.temperature > 0 {
+            elo + self.rng.random_range(0..self.temperature)
+        } else {
+            elo
+        }
+    }
+
+    fn evaluate(&mut self, split: &[bool]) -> Candidate {
+        let (left, right): (Vec<_>, Vec<_>) =
+            self.players.iter().zip(split).partition_map(|(p, &left)| {
+                if left {
+                    Either::Left(p)
+                } else {
+                    Either::Right(p)
+                }
+            });
+        let info = calculate_teams_elo_internal(
+            &left,
+            &right,
+            self.lose_streaks,
+            self.options,
+            self.model,
+        );
+        let novelty_penalty = self.novelty_penalty(split);
+        let diff = (self.apply_temperature(info.0.pity_elo)
+            - self.apply_temperature(info.1.pity_elo))
+        .abs()
+            + novelty_penalty;
+        Candidate {
+            split: split.to_vec(),
+            diff,
+            novelty_penalty,
+            info,
+        }
+    }
+
+    /// Penalty is non-negative, so pruning on elo difference alone stays safe.
+    fn novelty_penalty(&self, split: &[bool]) -> i32 {
+        let overlap: f64 = self
+            .recent_lineups
+            .iter()
+            .map(|lineup| lineup_overlap(split, lineup))
+            .sum();
+        (overlap * self.options.shuffle.novelty_weight.max(0) as f64).round() as i32
+    }
+
+    /// Keeps the candidate if it is among the best distinct splits so far. Mirrored splits are
+    /// considered the same.
+    fn offer(&mut self, candidate: Candidate) {
+        let same_split = |other: &Candidate| {
+            let mirrored = other.split[0] != candidate.split[0];
+            other
+                .split
+                .iter()
+                .zip(&candidate.split)
+                .all(|(a, b)| (a == b) != mirrored)
+        };
+        if let Some(i) = self.best.iter().position(same_split) {
+            if self.best[i].diff <= candidate.diff {
+                return;
+            }
+            self.best.remove(i);
+        }
+        let i = self.best.partition_point(|b| b.diff <= candidate.diff);
+        self.best.insert(i, candidate);
+        self.best.truncate(self.alternatives);
+    }
+
+    /// Difference a candidate has to beat to be kept.
+    fn best_diff(&self) -> i32 {
+        if self.best.len() < self.alternatives {
+            i32::MAX
+        } else {
+            self.best.last().map_or(i32::MAX, |b| b.diff)
+        }
+    }
+
+    /// Upper bound of the difference between the balanced team elo and the plain sum of player
+    /// elo, used to make pruning on plain sums safe.
+    fn adjustment_bound(&self) -> i32 {
+        let synergy: f64 = self.model.synergies().iter().map(|s| s.synergy.abs()).sum();
+        let max_team_elo = {
+            let mut elos: Vec<_> = self.players.iter().map(|p| p.elo.abs()).collect();
+            elos.sort_unstable_by(|a, b| b.cmp(a));
+            elos.iter()
+                .take(self.players.len().div_ceil(2))
+                .sum::<i32>()
+                + synergy.ceil() as i32
+        };
+        let streaks = self
+            .players
+            .iter()
+            .map(|p| self.lose_streaks.get(&p.id).copied().unwrap_or(0).max(0));
+        // Team streak of every pity mode is at most the sum of all streaks; per player bonuses
+        // may round differently for every player.
+        let (max_lose_streak, rounding) = match self.options.pity_bonus.mode {
+            PityMode::MaxStreak | PityMode::AverageStreak => (streaks.max().unwrap_or(0), 0),
+            PityMode::SumStreaks => (streaks.sum(), 0),
+            PityMode::PerPlayer => (streaks.sum(), self.players.len() as i32),
+        };
+        let (_, _, pity_elo) = apply_pity_bonus(max_team_elo, max_lose_streak, self.options);
+        let pity = (pity_elo - max_team_elo).abs() + rounding + 1;
+        // Both teams may get pity bonus, synergy and temperature; +2 covers rounding.
+        2 * pity + synergy.ceil() as i32 + self.temperature.max(0) + 2
+    }
+
+    fn branch_and_bound(&mut self) {
+        let n = self.players.len();
+        // Strongest players first make plain elo bounds tight early.
+        let order: Vec<usize> = (0..n)
+            .sorted_by_key(|&i| std::cmp::Reverse(self.players[i].elo))
+            .collect();
+        let slack = self.adjustment_bound();
+        for left_size in self.left_sizes() {
+            let mut state = BranchState {
+                depth: 0,
+                left_sum: 0,
+                right_sum: 0,
+                left_slots: left_size,
+                right_slots: n - left_size,
+            };
+            let mut assigned = vec![None; n];
+            // Mirrored splits are equivalent unless players are pinned, so the strongest player
+            // may always go left.
+            if !self.constraints.has_pins() {
+                if left_size == 0 {
+                    continue;
+                }
+                assigned[order[0]] = Some(true);
+                state.depth = 1;
+                state.left_sum = self.players[order[0]].elo;
+                state.left_slots -= 1;
+            }
+            self.branch(&order, &mut assigned, state, slack);
+        }
+    }
+
+    fn branch(
+        &mut self,
+        order: &[usize],
+        assigned: &mut [Option<bool>],
+        state: BranchState,
+        slack: i32,
+    ) {
+        if state.left_slots == 0 && state.right_slots == 0 {
+            let split: Vec<bool> = assigned.iter().map(|side| side.unwrap_or(false)).collect();
+            let candidate = self.evaluate(&split);
+            self.offer(candidate);
+            return;
+        }
+        let remaining: Vec<i32> = order[state.depth..]
+            .iter()
+            .map(|&i| self.players[i].elo)
+            .collect();
+        if plain_diff_bound(&state, &remaining) - slack >= self.best_diff() {
+            return;
+        }
+        let player = order[state.depth];
+        let elo = self.players[player].elo;
+        if state.left_slots > 0 && self.constraints.allows(player, true, assigned) {
+            assigned[player] = Some(true);
+            let next = BranchState {
+                depth: state.depth + 1,
+                left_sum: state.left_sum + elo,
+                left_slots: state.left_slots - 1,
+                ..state
+            };
+            self.branch(order, assigned, next, slack);
+        }
+        if state.right_slots > 0 && self.constraints.allows(player, false, assigned) {
+            assigned[player] = Some(false);
+            let next = BranchState {
+                depth: state.depth + 1,
+                right_sum: state.right_sum + elo,
+                right_slots: state.right_slots - 1,
+                ..state
+            };
+            self.branch(order, assigned, next, slack);
+        }
+        assigned[player] = None;
+    }
+
+    /// Random restarts of steepest descent. A move swaps two groups of players that always play
+    /// together (single players without such constraints) or moves one group to the other team,
+    /// as long as team sizes and constraints stay valid.
+    fn local_search(&mut self, restarts: usize) {
+        let groups = self.constraints.groups().to_vec();
+        for _ in 0..restarts {
+            let Some(split) = self.random_split(&groups) else {
+                continue;
+            };
+            let mut current = self.evaluate(&split);
+            loop {
+                let mut best_move: Option<Candidate> = None;
+                for (a, b) in (0..groups.len()).tuple_combinations().chain(
+                    // Moving a single group changes team sizes, which only fits odd counts.
+                    (0..groups.len()).map(|a| (a, a)),
+                ) {
+                    let side = |g: usize| current.split[groups[g][0]];
+                    if a != b && side(a) == side(b) {
+                        continue;
+                    }
+                    let mut neighbour = current.split.clone();
+                    for &i in groups[a]
+                        .iter()
+                        .chain(if a != b { &groups[b] } else { &[][..] })
+                    {
+                        neighbour[i] = !neighbour[i];
+                    }
+                    if !self.valid(&neighbour) {
+                        continue;
+                    }
+                    let candidate = self.evaluate(&neighbour);
+                    // Neighbours not taken may still make good alternatives.
+                    if best_move.as_ref().map_or(current.diff, |b| b.diff) > candidate.diff {
+                        if let Some(previous) = best_move.replace(candidate) {
+                            self.offer(previous);
+                        }
+                    } else {
+                        self.offer(candidate);
+                    }
+                }
+                match best_move {
+                    Some(next) => self.offer(std::mem::replace(&mut current, next)),
+                    None => break,
+                }
+            }
+            debug!("Local search restart finished with diff {}", current.diff);
+            self.offer(current);
+        }
+    }
+
+    fn valid(&self, split: &[bool]) -> bool {
+        let left = split.iter().filter(|&&l| l).count();
+        self.left_sizes().contains(&left) && self.constraints.satisfied(split)
+    }
+
+    /// Assigns groups in random order to random sides that still have room and keep
+    /// constraints satisfied. May fail even when a valid split exists.
+    fn random_split(&mut self, groups: &[Vec<usize>]) -> Option<Vec<bool>> {
+        let n = self.players.len();
+        let left_size = *self.left_sizes().choose(&mut self.rng)?;
+        let mut slots = [left_size, n - left_size];
+        let mut assigned = vec![None; n];
+        for group in groups.choose_multiple(&mut self.rng, groups.len()) {
+            let mut sides = [true, false];
+            sides.shuffle(&mut self.rng);
+            let side = sides.into_iter().find(|&left| {
+                slots[usize::from(!left)] >= group.len()
+                    && group
+                        .iter()
+                        .all(|&i| self.constraints.allows(i, left, &assigned))
+            })?;
+            slots[usize::from(!side)] -= group.len();
+            for &i in group {
+                assigned[i] = Some(side);
+            }
+        }
+        assigned.into_iter().collect()
+    }
+}
+
+fn recent_lineups(
+    players: &[PlayerWithElo],
+    history: &[HistoryEntry],
+    options: &SpaweloOptions,
+) -> Vec<Vec<Option<bool>>> {
+    let matches = options.shuffle.novelty_matches.max(0) as usize;
+    if matches == 0 || options.shuffle.novelty_weight <= 0 {
+        return Vec::new();
+    }
+    history
+        .iter()
+        .rev()
+        .filter(|entry| !entry.fake)
+        .take(matches)
+        .map(|entry| {
+            players
+                .iter()
+                .map(|p| {
+                    if entry.winner.contains(&p.id) {
+                        Some(true)
+                    } else if entry.loser.contains(&p.id) {
+                        Some(false)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// How much a split repeats a past lineup: 1.0 when the teams are the same (on either side),
+/// 0.0 when players are mixed as much as possible. Players absent from the past match count as
+/// not repeating.
+fn lineup_overlap(split: &[bool], lineup: &[Option<bool>]) -> f64 {
+    let (same, swapped) =
+        split
+            .iter()
+            .zip(lineup)
+            .fold((0, 0), |(same, swapped), (&left, &side)| match side {
+                Some(side) if side == left => (same + 1, swapped),
+                Some(_) => (same, swapped + 1),
+                None => (same, swapped),
+            });
+    let n = split.len().max(1) as f64;
+    (2.0 * same.max(swapped) as f64 / n - 1.0).max(0.0)
+}
+
+#[derive(Clone, Copy)]
+struct BranchState {
+    depth: usize,
+    left_sum: i32,
+    right_sum: i32,
+    left_slots: usize,
+    right_slots: usize,
+}
+
+/// Lower bound of plain elo difference over all completions of a partial split.
+///
+/// `remaining` must be sorted descending. Difference is linear in the sum of players going left,
+/// which lies between the sums of the weakest and the strongest `left_slots` players.
+fn plain_diff_bound(state: &BranchState, remaining: &[i32]) -> i32 {
+    let total: i32 = remaining.iter().sum();
+    let max_left: i32 = remaining[..state.left_slots].iter().sum();
+    let min_left: i32 = remaining[remaining.len() - state.left_slots..].iter().sum();
+    let diff = |left: i32| state.left_sum + left - state.right_sum - (total - left);
+    let (low, high) = (diff(min_left), diff(max_left));
+    if low <= 0 && high >= 0 {
+        0
+    } else {
+        low.abs().min(high.abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::{ConstraintsOptions, ShuffleOptions};
+    use crate::MlElo;
+
+    use super::*;
+
+    fn players(elos: &[i32]) -> Vec<PlayerWithElo> {
+        elos.iter()
+            .enumerate()
+            .map(|(i, &elo)| PlayerWithElo {
+                id: PlayerId::from(format!("p{i}").as_str()),
+                elo,
+            })
+            .collect()
+    }
+
+    fn options(exact_max_players: i32) -> SpaweloOptions {
+        SpaweloOptions {
+            shuffle: ShuffleOptions {
+                exact_max_players,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn best_split(players: &[PlayerWithElo], options: &SpaweloOptions) -> TeamSplit {
+        let splits = shuffle_teams(
+            players.to_vec(),
+            &HashMap::new(),
+            &[],
+            0,
+            0,
+            options,
+            &MlElo::default(),
+        )
+        .unwrap();
+        splits.into_iter().next().unwrap()
+    }
+
+    fn brute_force_diff(players: &[PlayerWithElo]) -> i32 {
+        let total: i32 = players.iter().map(|p| p.elo).sum();
+        (1..=players.len() / 2 + 1)
+            .flat_map(|size| players.iter().combinations(size))
+            .filter(|team| team.len().abs_diff(players.len() - team.len()) <= 1)
+            .map(|team| (total - 2 * team.iter().map(|p| p.elo).sum::<i32>()).abs())
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn exact_search_finds_optimum() {
+        let players = players(&[1500, 1210, 1190, 1100, 1020, 990, 940, 870, 860, 700]);
+        let TeamSplit { left, right, .. } = best_split(&players, &options(16));
+        assert_eq!(left.players.len(), 5);
+        assert_eq!(right.players.len(), 5);
+        assert_eq!(
+            (left.real_elo - right.real_elo).abs(),
+            brute_force_diff(&players)
+        );
+    }
+
+    #[test]
+    fn odd_player_count() {
+        let players = players(&[2000, 1000, 1000, 900, 100]);
+        let TeamSplit { left, right, .. } = best_split(&players, &options(16));
+        assert_eq!(left.players.len().abs_diff(right.players.len()), 1);
+        assert_eq!(left.players.len() + right.players.len(), 5);
+        assert_eq!(
+            (left.real_elo - right.real_elo).abs(),
+            brute_force_diff(&players)
+        );
+    }
+
+    #[test]
+    fn heuristic_for_large_lobby() {
+        let elos: Vec<i32> = (0..24).map(|i| 800 + (i * 37) % 500).collect();
+        let players = players(&elos);
+        let TeamSplit { left, right, .. } = best_split(&players, &options(16));
+        assert_eq!(left.players.len(), 12);
+        assert_eq!(right.players.len(), 12);
+        assert!((left.real_elo - right.real_elo).abs() <= 10);
+    }
+
+    #[test]
+    fn constraints_are_respected() {
+        let players = players(&[1500, 1400, 1100, 1000, 900, 800, 700, 600, 500]);
+        let id = |i: usize| players[i].id.clone();
+        for exact_max_players in [16, 0] {
+            let options = SpaweloOptions {
+                constraints: ConstraintsOptions {
+                    never_together: "p2+p3".parse().unwrap(),
+                    always_together: "p0+p1".parse().unwrap(),
+                    pinned: "p8=right".parse().unwrap(),
+                },
+                ..options(exact_max_players)
+            };
+            let TeamSplit { left, right, .. } = best_split(&players, &options);
+            assert!(left.players.contains(&id(0)) == left.players.contains(&id(1)));
+            assert!(left.players.contains(&id(2)) != left.players.contains(&id(3)));
+            assert!(right.players.contains(&id(8)));
+            assert_eq!(left.players.len().abs_diff(right.players.len()), 1);
+        }
+    }
+
+    #[test]
+    fn unsatisfiable_constraints() {
+        let options = SpaweloOptions {
+            constraints: ConstraintsOptions {
+                pinned: "p0=left, p1=left, p2=left".parse().unwrap(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = shuffle_teams(
+            players(&[1000, 1000, 1000, 1000]),
+            &HashMap::new(),
+            &[],
+            0,
+            0,
+            &options,
+            &MlElo::default(),
+        );
+        assert_eq!(result.unwrap_err(), ShuffleError::Unsatisfiable);
+    }
+
+    #[test]
+    fn alternatives_are_distinct_and_sorted() {
+        let players = players(&[1300, 1200, 1000, 900]);
+        let splits = shuffle_teams(
+            players.clone(),
+            &HashMap::new(),
+            &[],
+            0,
+            0,
+            &options(16),
+            &MlElo::default(),
+        )
+        .unwrap();
+        // Only three distinct 2v2 splits exist
+        assert_eq!(splits.len(), 3);
+        let diffs: Vec<_> = splits.iter().map(|s| s.elo_diff).collect();
+        assert_eq!(diffs, vec![0, 200, 600]);
+        assert!((splits[0].win_chance - 0.5).abs() < 1e-9);
+
+        let options = SpaweloOptions {
+            shuffle: ShuffleOptions {
+                alternatives: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let splits = shuffle_teams(
+            players,
+            &HashMap::new(),
+            &[],
+            0,
+            0,
+            &options,
+            &MlElo::default(),
+        )
+        .unwrap();
+        assert_eq!(splits.len(), 2);
+    }
+
+    #[test]
+    fn recent_lineups_are_penalized() {
+        // p4 and p5 are interchangeable, so two perfectly balanced splits exist
+        let players = players(&[1300, 1200, 1000, 900, 1100, 1100]);
+        let names = |ids: &[usize]| {
+            ids.iter()
+                .map(|&i| players[i].id.as_str())
+                .collect::<Vec<_>>()
+        };
+        let history = vec![HistoryEntry::test_entry(
+            0,
+            names(&[0, 3, 4]),
+            names(&[1, 2, 5]),
+        )];
+        let options = SpaweloOptions {
+            shuffle: ShuffleOptions {
+                novelty_matches: 1,
+                novelty_weight: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let splits = shuffle_teams(
+            players.clone(),
+            &HashMap::new(),
+            &history,
+            0,
+            0,
+            &options,
+            &MlElo::default(),
+        )
+        .unwrap();
+        let best = &splits[0];
+        assert_eq!(best.elo_diff, 0);
+        // 4 of 6 players keep their teams
+        assert_eq!(best.novelty_penalty, 33);
+        assert!(best.left.players.contains(&players[5].id));
+    }
+
+    #[test]
+    fn overlap() {
+        let lineup = [Some(true), Some(true), Some(false), Some(false)];
+        assert_eq!(lineup_overlap(&[true, true, false, false], &lineup), 1.0);
+        assert_eq!(lineup_overlap(&[false, false, true, true], &lineup), 1.0);
+        assert_eq!(lineup_overlap(&[true, false, true, false], &lineup), 0.0);
+        let partial = [Some(true), None, Some(false), None];
+        assert_eq!(lineup_overlap(&[true, true, false, false], &partial), 0.0);
+        let partial = [Some(true), Some(true), Some(false), None];
+        assert_eq!(lineup_overlap(&[true, true, false, false], &partial), 0.5);
+    }
+
+    #[test]
+    fn same_seed_gives_same_splits() {
+        let elos: Vec<i32> = (0..24).map(|i| 800 + (i * 37) % 500).collect();
+        let shuffle = |seed| {
+            shuffle_teams(
+                players(&elos),
+                &HashMap::new(),
+                &[],
+                50,
+                seed,
+                &options(16),
+                &MlElo::default(),
+            )
+            .unwrap()
+        };
+        assert_eq!(shuffle(7), shuffle(7));
+    }
+
+    #[test]
+    fn plain_bound() {
+        let state = BranchState {
+            depth: 1,
+            left_sum: 1000,
+            right_sum: 0,
+            left_slots: 1,
+            right_slots: 2,
+        };
+        // Best completion: 100 left, 500 and 400 right -> 1100 - 900
+        assert_eq!(plain_diff_bound(&state, &[500, 400, 100]), 200);
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use eloelo_model::history::HistoryEntry;
+use eloelo_model::player::{Player, PlayerWithElo};
+use eloelo_model::{PlayerId, Team, WinScale};
+use log::info;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::options::SpaweloOptions;
+use crate::rating_model::{MlElo, RatingModel};
+use crate::shuffle::shuffle_teams;
+use crate::training::training_window;
+use crate::win_probability;
+
+/// Matches where the favourite's true win chance is below this are counted as close.
+const CLOSE_MATCH_CHANCE: f64 = 0.6;
+
+/// Synthetic league played by [`simulate`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct SimulationParams {
+    pub players: usize,
+    /// Players drawn from the league for every match
+    pub lobby_size: usize,
+    pub matches: usize,
+    /// Standard deviation of the hidden true skill, in elo
+    pub skill_spread: f64,
+    /// Ratings are refitted every this many matches
+    pub refit_interval: usize,
+    pub seed: u64,
+}
+
+impl Default for SimulationParams {
+    fn default() -> Self {
+        Self {
+            players: 16,
+            lobby_size: 10,
+            matches: 300,
+            skill_spread: 150.0,
+            refit_interval: 5,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum SimulationError {
+    #[error("At least 2 players are needed, got {0}")]
+    TooFewPlayers(usize),
+    #[error("Lobby size must be even and at least 2, got {0}")]
+    InvalidLobbySize(usize),
+    #[error("Lobby size {lobby_size} is larger than the league of {players} players")]
+    LobbyLargerThanLeague { lobby_size: usize, players: usize },
+}
+
+impl SimulationParams {
+    fn validate(&self) -> Result<(), SimulationError> {
+        if self.players < 2 {
+            return Err(SimulationError::TooFewPlayers(self.players));
+        }
+        if self.lobby_size < 2 || !self.lobby_size.is_multiple_of(2) {
+            return Err(SimulationError::InvalidLobbySize(self.lobby_size));
+        }
+        if self.lobby_size > self.players {
+            return Err(SimulationError::LobbyLargerThanLeague {
+                lobby_size: self.lobby_size,
+                players: self.players,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of one option set, see [`simulate`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationReport {
+    /// Root mean square difference of fitted ratings and true skill after the last match.
+    /// Both are centered first, as only rating differences matter.
+    pub rating_error: f64,
+    /// Rating error after every refit
+    pub rating_error_curve: Vec<RatingErrorPoint>,
+    /// Mean true win chance of the stronger team, 0.5 is a perfectly balanced match
+    pub mean_favourite_chance: f64,
+    /// Fraction of matches with the favourite's true win chance below 0.6
+    pub close_matches: f64,
+    /// Number of lose streaks by length, the first element counts streaks of a single loss
+    pub lose_streaks: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingErrorPoint {
+    pub matches: usize,
+    pub error: f64,
+}
+
+/// Plays the same synthetic league once for each of `option_sets`.
+///
+/// Players get a hidden true skill drawn from a normal distribution around the default elo.
+/// Every match takes a random lobby, splits it with [`shuffle_teams`] using ML ELO ratings
+/// and lose streaks accumulated so far, and draws the winner from the true skill of the teams.
+/// Ratings are refitted every `refit_interval` matches.
+///
+/// Players, lobbies and outcomes are drawn from `seed`, so option sets are compared on the same
+/// league. Constraints of option sets are ignored, as they name real players.
+pub fn simulate(
+    params: &SimulationParams,
+    option_sets: &[SpaweloOptions],
+) -> Result<Vec<SimulationReport>, SimulationError> {
+    params.validate()?;
+    let start = Instant::now();
+    let reports = option_sets
+        .iter()
+        .map(|options| {
+            let options = SpaweloOptions {
+                constraints: Default::default(),
+                ..options.clone()
+            };
+            simulate_league(params, &options)
+        })
+        .collect();
+    info!(
+        "Simulation of {} option sets took {:?}",
+        option_sets.len(),
+        start.elapsed()
+    );
+    Ok(reports)
+}
+
+fn simulate_league(params: &SimulationParams, options: &SpaweloOptions) -> SimulationReport {
+    let mut rng = StdRng::seed_from_u64(params.seed);
+    let skills: Vec<(PlayerId, f64)> = (0..params.players)
+        .map(|i| {
+            let skill = sample_normal(&mut rng, Player::default_elo() as f64, params.skill_spread);
+            (PlayerId::from(format!("player{i:02}")), skill)
+        })
+        .collect();
+    let true_skill: HashMap<_, _> = skills.iter().cloned().collect();
+    let team_skill = |team: &[PlayerId]| team.iter().map(|p| true_skill[p]).sum::<f64>();
+    let window = training_window(options);
+    let first_match: DateTime<Local> = Utc.timestamp_opt(0, 0).unwrap().into();
+
+    let mut report = SimulationReport::default();
+    let mut model = MlElo::default();
+    let mut history: Vec<HistoryEntry> = Vec::new();
+    let mut lose_streaks: HashMap<PlayerId, i32> = HashMap::new();
+    let mut favourite_chances = Vec::with_capacity(params.matches);
+    for i in 0..params.matches {
+        if i > 0 && i % params.refit_interval.max(1) == 0 {
+            model.fit(&history[i.saturating_sub(window)..], options);
+            report.rating_error_curve.push(RatingErrorPoint {
+                matches: i,
+                error: rating_error(&skills, &model.ratings()),
+            });
+        }
+
+        let ratings = model.ratings();
+        let lobby = skills
+            .choose_multiple(&mut rng, params.lobby_size)
+            .map(|(id, _)| PlayerWithElo {
+                id: id.clone(),
+                elo: ratings
+                    .get(id)
+                    .map_or(Player::default_elo(), |r| r.round() as i32),
+            });
+        let seed = rng.random();
+        let splits = shuffle_teams(lobby, &lose_streaks, &history, 0, seed, options, &model)
+            .expect("Shuffle without constraints always has a split");
+        let Some(split) = splits.into_iter().next() else {
+            break;
+        };
+
+        let (left, right) = (split.left.players, split.right.players);
+        let left_chance = win_probability(team_skill(&left), team_skill(&right));
+        favourite_chances.push(left_chance.max(1.0 - left_chance));
+        let (winner, loser, winner_side) = if rng.random::<f64>() < left_chance {
+            (left, right, Team::Left)
+        } else {
+            (right, left, Team::Right)
+        };
+        for player in &winner {
+            if let Some(streak) = lose_streaks.remove(player) {
+                count_lose_streak(&mut report.lose_streaks, streak);
+            }
+        }
+        for player in &loser {
+            *lose_streaks.entry(player.clone()).or_default() += 1;
+        }
+        history.push(HistoryEntry {
+            id: Default::default(),
+            timestamp: first_match + Duration::from_secs(i as u64 * 60 * 60),
+            winner,
+            loser,
+            scale: WinScale::Even,
+            duration: Duration::from_secs(40 * 60),
+            fake: false,
+            seed: Some(seed),
+            winner_side: Some(winner_side),
+            heroes: Default::default(),
+            offered_heroes: Default::default(),
+        });
+    }
+    for streak in lose_streaks.into_values() {
+        count_lose_streak(&mut report.lose_streaks, streak);
+    }
+
+    model.fit(&history[history.len().saturating_sub(window)..], options);
+    report.rating_error = rating_error(&skills, &model.ratings());
+    if !favourite_chances.is_empty() {
+        let n = favourite_chances.len() as f64;
+        report.mean_favourite_chance = favourite_chances.iter().sum::<f64>() / n;
+        report.close_matches = favourite_chances
+            .iter()
+            .filter(|&&c| c < CLOSE_MATCH_CHANCE)
+            .count() as f64
+            / n;
+    }
+    report
+}
+
+fn count_lose_streak(histogram: &mut Vec<usize>, streak: i32) {
+    let Some(index) = (streak as usize).checked_sub(1) else {
+        return;
+    };
+    if histogram.len() <= index {
+        histogram.resize(index + 1, 0);
+    }
+    histogram[index] += 1;
+}
+
+fn rating_error(skills: &[(PlayerId, f64)], ratings: &HashMap<PlayerId, f64>) -> f64 {
+    let n = skills.len() as f64;
+    let rating = |p: &PlayerId| {
+        ratings
+            .get(p)
+            .copied()
+            .unwrap_or(Player::default_elo() as f64)
+    };
+    let offset = skills.iter().map(|(p, s)| s - rating(p)).sum::<f64>() / n;
+    let squared_error: f64 = skills
+        .iter()
+        .map(|(p, s)| (rating(p) + offset - s).powi(2))
+        .sum();
+    (squared_error / n).sqrt()
+}
+
+/// Box-Muller transform.
+fn sample_normal(rng: &mut impl Rng, mean: f64, std_dev: f64) -> f64 {
+    let u1 = 1.0 - rng.random::<f64>();
+    let u2 = rng.random::<f64>();
+    mean + std_dev * (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::PityBonusOptions;
+
+    use super::*;
+
+    fn params() -> SimulationParams {
+        SimulationParams {
+            players: 10,
+            lobby_size: 6,
+            matches: 120,
+            refit_interval: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ratings_recover_true_skill() {
+        let options = SpaweloOptions::default();
+        let reports = simulate(&params(), &[options.clone(), options]).unwrap();
+        assert_eq!(reports[0], reports[1]);
+        let report = &reports[0];
+        assert_eq!(report.rating_error_curve.len(), 11);
+        assert!(report.rating_error < report.rating_error_curve[0].error);
+        assert!(report.mean_favourite_chance >= 0.5);
+        assert!((0.0..=1.0).contains(&report.close_matches));
+        // Every loss belongs to exactly one streak
+        let losses: usize = report
+            .lose_streaks
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (i + 1) * count)
+            .sum();
+        assert_eq!(losses, 120 * 3);
+    }
+
+    #[test]
+    fn option_sets_share_the_league() {
+        let no_pity = SpaweloOptions {
+            pity_bonus: PityBonusOptions {
+                additive: false,
+                multiplicative: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let reports = simulate(&params(), &[SpaweloOptions::default(), no_pity]).unwrap();
+        assert_eq!(reports.len(), 2);
+        // Pity bonus changes the splits and so the outcomes
+        assert_ne!(reports[0], reports[1]);
+    }
+
+    #[test]
+    fn invalid_params() {
+        let simulate = |params| simulate(&params, &[SpaweloOptions::default()]);
+        let params = params();
+        assert_eq!(
+            simulate(SimulationParams {
+                players: 1,
+                ..params.clone()
+            }),
+            Err(SimulationError::TooFewPlayers(1))
+        );
+        for lobby_size in [0, 5] {
+            assert_eq!(
+                simulate(SimulationParams {
+                    lobby_size,
+                    ..params.clone()
+                }),
+                Err(SimulationError::InvalidLobbySize(lobby_size))
+            );
+        }
+        assert_eq!(
+            simulate(SimulationParams {
+                lobby_size: 12,
+                ..params
+            }),
+            Err(SimulationError::LobbyLargerThanLeague {
+                lobby_size: 12,
+                players: 10
+            })
+        );
+    }
+}
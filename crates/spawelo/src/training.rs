@@ -0,0 +1,110 @@
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use eloelo_model::history::HistoryEntry;
+
+use crate::options::{MlEloOptions, SpaweloOptions};
+
+/// Number of most recent matches the model is fitted on, see `max_elo_history`.
+pub fn training_window(options: &SpaweloOptions) -> usize {
+    match options.ml_elo.max_elo_history {
+        n if n <= 0 => usize::MAX,
+        n => n as usize,
+    }
+}
+
+/// Fake matches played before the returned time no longer count, see `fake_match_max_days`.
+pub fn fake_match_deadline(options: &MlEloOptions, now: DateTime<Local>) -> DateTime<Local> {
+    if options.fake_match_max_days > 0 {
+        now.checked_sub_signed(TimeDelta::days(options.fake_match_max_days.into()))
+            .unwrap_or_else(|| DateTime::<Utc>::MIN_UTC.into())
+    } else {
+        // Make sure all fake matches will be outdated on invalid max_days
+        now + TimeDelta::hours(1)
+    }
+}
+
+pub fn without_outdated_fakes<'a>(
+    history: impl IntoIterator<Item = &'a HistoryEntry>,
+    options: &MlEloOptions,
+    now: DateTime<Local>,
+) -> Vec<HistoryEntry> {
+    let fake_deadline = fake_match_deadline(options, now);
+    history
+        .into_iter()
+        .filter(|e| !e.fake || e.timestamp >= fake_deadline)
+        .cloned()
+        .collect()
+}
+
+/// Matches ratings are fitted on: the most recent ones within the training window, without
+/// outdated fakes.
+pub fn training_history<'a>(
+    history: impl IntoIterator<Item = &'a HistoryEntry>,
+    options: &SpaweloOptions,
+    now: DateTime<Local>,
+) -> Vec<HistoryEntry> {
+    let mut history = without_outdated_fakes(history, &options.ml_elo, now);
+    let outside_window = history.len().saturating_sub(training_window(options));
+    history.drain(..outside_window);
+    history
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn fake_match_deadline_counts_days() {
+        let now = Local.timestamp_opt(1_000_000_000, 0).unwrap();
+        let mut options = MlEloOptions {
+            fake_match_max_days: 30,
+            ..Default::default()
+        };
+        assert_eq!(
+            fake_match_deadline(&options, now),
+            now - TimeDelta::days(30)
+        );
+
+        options.fake_match_max_days = i32::MAX;
+        assert!(fake_match_deadline(&options, now) < now);
+
+        options.fake_match_max_days = 0;
+        assert!(fake_match_deadline(&options, now) > now);
+    }
+
+    #[test]
+    fn outdated_fakes_and_old_matches_are_left_out() {
+        let now = Local.timestamp_opt(1_000_000, 0).unwrap();
+        let mut options = SpaweloOptions::default();
+        options.ml_elo.max_elo_history = 3;
+        let deadline = fake_match_deadline(&options.ml_elo, now).timestamp();
+        let history: Vec<_> = [(-2, false), (-1, true), (0, true), (1, false), (2, false)]
+            .into_iter()
+            .map(|(offset, fake)| HistoryEntry {
+                fake,
+                ..HistoryEntry::test_entry(deadline + offset, ["a"], ["b"])
+            })
+            .collect();
+        let offsets = |history: Vec<HistoryEntry>| -> Vec<i64> {
+            history
+                .iter()
+                .map(|e| e.timestamp.timestamp() - deadline)
+                .collect()
+        };
+        assert_eq!(
+            offsets(without_outdated_fakes(&history, &options.ml_elo, now)),
+            [-2, 0, 1, 2]
+        );
+        assert_eq!(
+            offsets(training_history(&history, &options, now)),
+            [0, 1, 2]
+        );
+
+        options.ml_elo.fake_match_max_days = 0;
+        assert_eq!(
+            offsets(without_outdated_fakes(&history, &options.ml_elo, now)),
+            [-2, 1, 2]
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::f64::consts::{PI, SQRT_2};
+
+use chrono::{DateTime, Local};
+use eloelo_model::history::HistoryEntry;
+use eloelo_model::player::{Player, PlayerWithElo};
+use eloelo_model::{PlayerId, Team, WinScale};
+use log::info;
+
+use crate::options::{RatingModelKind, SpaweloOptions, TrueSkillOptions};
+use crate::rating_model::RatingModel;
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkillEstimate {
+    pub mean: f64,
+    pub deviation: f64,
+}
+
+#[derive(Debug, Clone)]
+struct PlayerSkill {
+    estimate: SkillEstimate,
+    last_played: DateTime<Local>,
+}
+
+/// Bayesian rating model in the spirit of TrueSkill.
+///
+/// Every player has a normally distributed skill estimate. Matches are processed in order and
+/// update the estimates of all participants, scaled by the win margin. Deviation grows with
+/// time spent away from the game, so occasional players are rated with less confidence.
+///
+/// With [`TrueSkillOptions::side_advantage`] the left side is rated like an additional
+/// player of the left team, from matches with recorded sides.
+#[derive(Debug, Clone, Default)]
+pub struct TrueSkill {
+    skills: HashMap<PlayerId, PlayerSkill>,
+    side: Option<SkillEstimate>,
+    options: TrueSkillOptions,
+}
+
+impl TrueSkill {
+    pub fn estimate(&self, player: &PlayerId) -> Option<SkillEstimate> {
+        self.skills.get(player).map(|s| s.estimate)
+    }
+
+    fn initial_estimate(&self) -> SkillEstimate {
+        SkillEstimate {
+            mean: Player::default_elo() as f64,
+            deviation: self.options.initial_deviation as f64,
+        }
+    }
+
+    fn estimate_at(&self, player: &PlayerId, time: DateTime<Local>) -> SkillEstimate {
+        let Some(skill) = self.skills.get(player) else {
+            return self.initial_estimate();
+        };
+        let idle_days = (time - skill.last_played).num_seconds().max(0) as f64 / SECONDS_PER_DAY;
+        let growth = self.options.deviation_growth_per_day.as_f64();
+        let deviation = (skill.estimate.deviation.powi(2) + growth.powi(2) * idle_days)
+            .sqrt()
+            .min(self.options.initial_deviation as f64);
+        SkillEstimate {
+            mean: skill.estimate.mean,
+            deviation,
+        }
+    }
+
+    fn margin_factor(&self, scale: WinScale) -> f64 {
+        match scale {
+            WinScale::Even => 1.0,
+            WinScale::Advantage => self.options.advantage_margin_factor.as_f64(),
+            WinScale::Pwnage => self.options.pwnage_margin_factor.as_f64(),
+        }
+    }
+
+    /// Estimate of the left side bonus with the sign it has for a team playing on `side`.
+    fn side_estimate(&self, side: Option<Team>) -> Option<(SkillEstimate, f64)> {
+        let estimate = self.side?;
+        match side? {
+            Team::Left => Some((estimate, 1.0)),
+            Team::Right => Some((estimate, -1.0)),
+        }
+    }
+
+    fn update(&mut self, entry: &HistoryEntry) {
+        let winner: Vec<_> = entry
+            .winner
+            .iter()
+            .map(|p| self.estimate_at(p, entry.timestamp))
+            .collect();
+        let loser: Vec<_> = entry
+            .loser
+            .iter()
+            .map(|p| self.estimate_at(p, entry.timestamp))
+            .collect();
+
+        let side = self.side_estimate(entry.winner_side);
+        let c = self.performance_deviation(winner.iter().chain(&loser), side.map(|(e, _)| e));
+        let bonus = side.map_or(0.0, |(e, sign)| sign * e.mean);
+        let t = (team_mean(&winner) + bonus - team_mean(&loser)) / c;
+        let v = normal_pdf(t) / normal_cdf(t);
+        let w = v * (v + t);
+        let margin = self.margin_factor(entry.scale);
+
+        let updated = |e: &SkillEstimate, sign: f64| {
+            let variance = e.deviation.powi(2);
+            SkillEstimate {
+                mean: e.mean + sign * margin * variance / c * v,
+                deviation: (variance * (1.0 - variance / c.powi(2) * w).max(0.0001)).sqrt(),
+            }
+        };
+        let updates: Vec<_> = entry
+            .winner
+            .iter()
+            .zip(winner.iter().map(|e| updated(e, 1.0)))
+            .chain(
+                entry
+                    .loser
+                    .iter()
+                    .zip(loser.iter().map(|e| updated(e, -1.0))),
+            )
+            .collect();
+        if let Some((estimate, sign)) = side {
+            self.side = Some(updated(&estimate, sign));
+        }
+        for (player, estimate) in updates {
+            self.skills.insert(
+                player.clone(),
+                PlayerSkill {
+                    estimate,
+                    last_played: entry.timestamp,
+                },
+            );
+        }
+    }
+
+    /// Combined deviation of the match outcome. The side has no performance of its own.
+    fn performance_deviation<'a>(
+        &self,
+        players: impl Iterator<Item = &'a SkillEstimate>,
+        side: Option<SkillEstimate>,
+    ) -> f64 {
+        let beta = self.options.performance_deviation as f64;
+        let variance = players
+            .map(|e| e.deviation.powi(2) + beta.powi(2))
+            .sum::<f64>()
+            + side.map_or(0.0, |e| e.deviation.powi(2));
+        variance.sqrt().max(1.0)
+    }
+
+    fn team_estimates(&self, team: &[PlayerWithElo]) -> Vec<SkillEstimate> {
+        team.iter()
+            .map(|p| match self.skills.get(&p.id) {
+                Some(s) => s.estimate,
+                None => SkillEstimate {
+                    mean: p.elo as f64,
+                    ..self.initial_estimate()
+                },
+            })
+            .collect()
+    }
+}
+
+impl RatingModel for TrueSkill {
+    fn kind(&self) -> RatingModelKind {
+        RatingModelKind::TrueSkill
+    }
+
+    fn fit(&mut self, history: &[HistoryEntry], options: &SpaweloOptions) {
+        self.skills.clear();
+        self.options = options.true_skill.clone();
+        self.side = self.options.side_advantage.then_some(SkillEstimate {
+            mean: 0.0,
+            deviation: self.options.initial_deviation as f64,
+        });
+        info!(
+            "Calculating TrueSkill from {} historic matches",
+            history.len()
+        );
+
+        for entry in history {
+            self.update(entry);
+        }
+
+        // Account for the time players spent away since their last match.
+        if let Some(last) = history.last() {
+            let players: Vec<_> = self.skills.keys().cloned().collect();
+            for player in players {
+                let estimate = self.estimate_at(&player, last.timestamp);
+                if let Some(skill) = self.skills.get_mut(&player) {
+                    skill.estimate = estimate;
+                    skill.last_played = last.timestamp;
+                }
+            }
+        }
+    }
+
+    fn ratings(&self) -> HashMap<PlayerId, f64> {
+        self.skills
+            .iter()
+            .map(|(p, s)| (p.clone(), s.estimate.mean))
+            .collect()
+    }
+
+    fn uncertainty(&self, player: &PlayerId) -> Option<f64> {
+        self.estimate(player).map(|e| e.deviation)
+    }
+
+    fn side_advantage(&self) -> Option<f64> {
+        self.side.map(|e| e.mean)
+    }
+
+    fn win_probability(
+        &self,
+        lhs: &[PlayerWithElo],
+        rhs: &[PlayerWithElo],
+        lhs_side: Option<Team>,
+    ) -> f64 {
+        let lhs = self.team_estimates(lhs);
+        let rhs = self.team_estimates(rhs);
+        let side = self.side_estimate(lhs_side);
+        let c = self.performance_deviation(lhs.iter().chain(&rhs), side.map(|(e, _)| e));
+        let bonus = side.map_or(0.0, |(e, sign)| sign * e.mean);
+        normal_cdf((team_mean(&lhs) + bonus - team_mean(&rhs)) / c)
+    }
+}
+
+fn team_mean(team: &[SkillEstimate]) -> f64 {
+    team.iter().map(|e| e.mean).sum()
+}
+
+fn normal_pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * PI).sqrt()
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / SQRT_2)
+}
+
+// Complementary error function with fractional error below 1.2e-7 (Numerical Recipes, erfcc).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_entry(
+        day: i64,
+        winner: impl IntoIterator<Item = &'static str>,
+        loser: impl IntoIterator<Item = &'static str>,
+        scale: WinScale,
+    ) -> HistoryEntry {
+        HistoryEntry {
+            scale,
+            ..HistoryEntry::test_entry(day * 24 * 60 * 60, winner, loser)
+        }
+    }
+
+    fn fitted(history: &[HistoryEntry]) -> TrueSkill {
+        let mut model = TrueSkill::default();
+        model.fit(history, &Default::default());
+        model
+    }
+
+    #[test]
+    fn normal_cdf_values() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-6);
+        assert!((normal_cdf(1.0) - 0.841345).abs() < 1e-5);
+        assert!((normal_cdf(-1.0) - 0.158655).abs() < 1e-5);
+    }
+
+    #[test]
+    fn winner_gains_loser_loses() {
+        let model = fitted(&[make_entry(0, ["a", "b"], ["c", "d"], WinScale::Even)]);
+        let a = model.estimate(&PlayerId::from("a")).unwrap();
+        let c = model.estimate(&PlayerId::from("c")).unwrap();
+        assert!(a.mean > 1000.0);
+        assert!(c.mean < 1000.0);
+        assert!((a.mean - 1000.0 - (1000.0 - c.mean)).abs() < 1e-9);
+        assert!(a.deviation < 250.0);
+        assert!(c.deviation < 250.0);
+    }
+
+    #[test]
+    fn pwnage_moves_ratings_more() {
+        let even = fitted(&[make_entry(0, ["a"], ["b"], WinScale::Even)]);
+        let pwnage = fitted(&[make_entry(0, ["a"], ["b"], WinScale::Pwnage)]);
+        let a = PlayerId::from("a");
+        assert!(pwnage.estimate(&a).unwrap().mean > even.estimate(&a).unwrap().mean);
+    }
+
+    #[test]
+    fn occasional_player_is_less_certain() {
+        let mut history = Vec::new();
+        for day in 0..30 {
+            if day == 0 {
+                history.push(make_entry(
+                    day,
+                    ["regular", "occasional"],
+                    ["x", "y"],
+                    WinScale::Even,
+                ));
+            } else {
+                history.push(make_entry(
+                    day,
+                    ["regular", "z"],
+                    ["x", "y"],
+                    WinScale::Even,
+                ));
+            }
+        }
+        let model = fitted(&history);
+        let regular = model.uncertainty(&PlayerId::from("regular")).unwrap();
+        let occasional = model.uncertainty(&PlayerId::from("occasional")).unwrap();
+        assert!(occasional > regular);
+    }
+
+    #[test]
+    fn win_probability() {
+        let model = fitted(&[make_entry(0, ["a"], ["b"], WinScale::Even)]);
+        let a = PlayerWithElo {
+            id: PlayerId::from("a"),
+            elo: 0,
+        };
+        let b = PlayerWithElo {
+            id: PlayerId::from("b"),
+            elo: 0,
+        };
+        let a = [a];
+        let b = [b];
+        let p = model.win_probability(&a, &b, None);
+        assert!(p > 0.5);
+        assert!((p + model.win_probability(&b, &a, None) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn side_advantage() {
+        // Equal teams, left side wins three times out of four
+        let history: Vec<_> = (0..40)
+            .map(|day| {
+                let left_won = day % 4 != 3;
+                let (winner, loser) = if (day % 2 == 0) == left_won {
+                    (["a", "b"], ["c", "d"])
+                } else {
+                    (["c", "d"], ["a", "b"])
+                };
+                HistoryEntry {
+                    winner_side: Some(if left_won { Team::Left } else { Team::Right }),
+                    ..make_entry(day, winner, loser, WinScale::Even)
+                }
+            })
+            .collect();
+        let options = SpaweloOptions {
+            true_skill: TrueSkillOptions {
+                side_advantage: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut model = TrueSkill::default();
+        model.fit(&history, &options);
+        assert!(model.side_advantage().unwrap() > 50.0);
+
+        let team = |players: [&str; 2]| {
+            players.map(|p| PlayerWithElo {
+                id: PlayerId::from(p),
+                elo: 0,
+            })
+        };
+        let (ab, cd) = (team(["a", "b"]), team(["c", "d"]));
+        let unknown = model.win_probability(&ab, &cd, None);
+        assert!(model.win_probability(&ab, &cd, Some(Team::Left)) > unknown + 0.05);
+        assert!(model.win_probability(&ab, &cd, Some(Team::Right)) < unknown - 0.05);
+
+        assert_eq!(fitted(&history).side_advantage(), None);
+    }
+}
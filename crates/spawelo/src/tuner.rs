@@ -0,0 +1,185 @@
+use std::time::Instant;
+
+use eloelo_model::decimal::Decimal;
+use eloelo_model::history::HistoryEntry;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::backtest::{backtest, BacktestReport};
+use crate::options::{MlEloOptions, RatingModelKind, SpaweloOptions};
+
+const MAX_ROUNDS: usize = 3;
+const TARGET_PROBABILITIES: [&str; 9] = [
+    "0.55", "0.6", "0.65", "0.7", "0.75", "0.8", "0.85", "0.9", "0.95",
+];
+const MAX_ELO_HISTORY: [i32; 5] = [0, 50, 100, 200, 400];
+const WEIGHT_DECAY: [&str; 5] = ["0", "0.0005", "0.001", "0.002", "0.005"];
+
+/// Recommended ML ELO options together with backtests before and after tuning.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TuningResult {
+    pub options: MlEloOptions,
+    pub baseline: BacktestReport,
+    pub tuned: BacktestReport,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Parameter {
+    EvenTarget,
+    AdvantageTarget,
+    PwnageTarget,
+    MaxEloHistory,
+    WeightDecay,
+}
+
+impl Parameter {
+    const ALL: [Parameter; 5] = [
+        Parameter::EvenTarget,
+        Parameter::AdvantageTarget,
+        Parameter::PwnageTarget,
+        Parameter::MaxEloHistory,
+        Parameter::WeightDecay,
+    ];
+
+    fn candidates(self, options: &MlEloOptions) -> Vec<MlEloOptions> {
+        let with_decimal = |set: fn(&mut MlEloOptions, Decimal), values: &[&str]| {
+            values
+                .iter()
+                .map(|v| {
+                    let mut candidate = options.clone();
+                    set(&mut candidate, Decimal::new(v));
+                    candidate
+                })
+                .collect::<Vec<_>>()
+        };
+        let candidates = match self {
+            Parameter::EvenTarget => with_decimal(
+                |o, v| o.even_match_target_probability = v,
+                &TARGET_PROBABILITIES,
+            ),
+            Parameter::AdvantageTarget => with_decimal(
+                |o, v| o.advantage_match_target_probability = v,
+                &TARGET_PROBABILITIES,
+            ),
+            Parameter::PwnageTarget => with_decimal(
+                |o, v| o.pwnage_match_target_probability = v,
+                &TARGET_PROBABILITIES,
+            ),
+            Parameter::MaxEloHistory => MAX_ELO_HISTORY
+                .iter()
+                .map(|&max_elo_history| MlEloOptions {
+                    max_elo_history,
+                    ..options.clone()
+                })
+                .collect(),
+            Parameter::WeightDecay => with_decimal(|o, v| o.weight_decay = v, &WEIGHT_DECAY),
+        };
+        candidates.into_iter().filter(targets_ordered).collect()
+    }
+}
+
+// More decisive wins must map to higher target probabilities.
+fn targets_ordered(options: &MlEloOptions) -> bool {
+    options.even_match_target_probability < options.advantage_match_target_probability
+        && options.advantage_match_target_probability < options.pwnage_match_target_probability
+}
+
+/// Searches target probabilities, `max_elo_history` and weight decay of the ML ELO model
+/// for the lowest walk-forward log loss.
+///
+/// Uses coordinate descent starting from the current options: each parameter is optimized in
+/// turn while others stay fixed, until a round brings no improvement.
+pub fn tune_ml_elo(
+    history: &[HistoryEntry],
+    options: &SpaweloOptions,
+    warmup: usize,
+) -> TuningResult {
+    let start = Instant::now();
+    let evaluate = |ml_elo: &MlEloOptions| {
+        let options = SpaweloOptions {
+            ml_elo: ml_elo.clone(),
+            ..options.clone()
+        };
+        backtest(history, &options, RatingModelKind::MlElo, warmup)
+    };
+
+    let baseline = evaluate(&options.ml_elo);
+    let mut best = (options.ml_elo.clone(), baseline.clone());
+    for round in 0..MAX_ROUNDS {
+        let mut improved = false;
+        for parameter in Parameter::ALL {
+            for candidate in parameter.candidates(&best.0) {
+                if candidate == best.0 {
+                    continue;
+                }
+                let report = evaluate(&candidate);
+                debug!(
+                    "Tuning round {round}, {parameter:?}: log loss {:.5}",
+                    report.log_loss
+                );
+                if report.log_loss < best.1.log_loss {
+                    best = (candidate, report);
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    info!(
+        "Tuning took {:?}, log loss {:.5} -> {:.5}",
+        start.elapsed(),
+        baseline.log_loss,
+        best.1.log_loss
+    );
+
+    TuningResult {
+        options: best.0,
+        baseline,
+        tuned: best.1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates_keep_targets_ordered() {
+        let options = MlEloOptions::default();
+        for parameter in Parameter::ALL {
+            let candidates = parameter.candidates(&options);
+            assert!(!candidates.is_empty());
+            assert!(candidates.iter().all(targets_ordered));
+        }
+        // Even target must stay below default advantage target of 0.85
+        assert_eq!(Parameter::EvenTarget.candidates(&options).len(), 6);
+    }
+
+    #[test]
+    fn tuning_does_not_change_options_without_history() {
+        let result = tune_ml_elo(&[], &SpaweloOptions::default(), 5);
+        assert_eq!(result.options, MlEloOptions::default());
+        assert_eq!(result.tuned, result.baseline);
+    }
+
+    #[test]
+    fn tuning_follows_changing_skill() {
+        // Teams swap strength midway, which only a limited history window picks up quickly
+        let history: Vec<_> = (0..80)
+            .map(|day| {
+                let (winner, loser) = if day < 30 {
+                    (["a", "b"], ["c", "d"])
+                } else {
+                    (["c", "d"], ["a", "b"])
+                };
+                HistoryEntry::test_entry(day * 24 * 60 * 60, winner, loser)
+            })
+            .collect();
+        let result = tune_ml_elo(&history, &SpaweloOptions::default(), 10);
+        assert_eq!(result.options.max_elo_history, 50);
+        assert!(result.tuned.log_loss < result.baseline.log_loss);
+    }
+}
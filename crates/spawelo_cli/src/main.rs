@@ -0,0 +1,486 @@
+//! Offline analysis of `*.history.json` files with the same spawelo options as the app.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use clap::{Parser, Subcommand, ValueEnum};
+use eloelo_model::history::{History, HistoryEntry, HistorySerializeWrapper};
+use eloelo_model::migration;
+use eloelo_model::player::{Player, PlayerWithElo};
+use eloelo_model::{GameId, PlayerId, Team};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use spawelo::{
+    calculate_teams_elo, new_rating_model, shuffle_teams, simulate, training_history, RatingModel,
+    RatingModelKind, SimulationParams, SimulationReport, SpaweloOptions, TeamSplit,
+};
+
+#[derive(Parser)]
+#[command(about = "Offline spawelo analysis of eloelo history files")]
+struct Cli {
+    /// History file in the `*.history.json` format, can be given multiple times.
+    /// Required by all commands but `simulate`.
+    #[arg(long, global = true)]
+    history: Vec<PathBuf>,
+    /// Game to analyse, required when history files contain more than one game
+    #[arg(long, global = true)]
+    game: Option<String>,
+    /// eloelo options.yaml, only its `spawelo` section is used. Defaults when not given.
+    #[arg(long, global = true)]
+    options: Option<PathBuf>,
+    /// Rating model, overrides the one selected for the game in options
+    #[arg(long, global = true, value_parser = parse_rating_model)]
+    model: Option<RatingModelKind>,
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Writes the result to a file instead of stdout
+    #[arg(long, global = true)]
+    output: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Current ratings, best first
+    Ratings {
+        /// Players to show, everyone when empty
+        players: Vec<String>,
+    },
+    /// Win chance of the left team
+    Predict {
+        #[arg(long, value_delimiter = ',', required = true)]
+        left: Vec<String>,
+        #[arg(long, value_delimiter = ',', required = true)]
+        right: Vec<String>,
+    },
+    /// Team splits for the given players, best first
+    Shuffle {
+        #[arg(required = true)]
+        players: Vec<String>,
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        #[arg(long, default_value_t = 0)]
+        temperature: i32,
+    },
+    /// Compares options on a synthetic league of players with hidden true skill
+    Simulate {
+        /// eloelo options.yaml files to compare, defaults when none given
+        option_files: Vec<PathBuf>,
+        #[arg(long, default_value_t = SimulationParams::default().players)]
+        players: usize,
+        #[arg(long, default_value_t = SimulationParams::default().lobby_size)]
+        lobby_size: usize,
+        #[arg(long, default_value_t = SimulationParams::default().matches)]
+        matches: usize,
+        /// Standard deviation of the true skill, in elo
+        #[arg(long, default_value_t = SimulationParams::default().skill_spread)]
+        skill_spread: f64,
+        #[arg(long, default_value_t = SimulationParams::default().refit_interval)]
+        refit_interval: usize,
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// Subset of the app options file.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct OptionsFile {
+    spawelo: SpaweloOptions,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rating {
+    player: PlayerId,
+    rating: i32,
+    uncertainty: Option<i32>,
+    matches: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Prediction {
+    left: Vec<PlayerId>,
+    right: Vec<PlayerId>,
+    left_elo: i32,
+    right_elo: i32,
+    left_pity_elo: i32,
+    right_pity_elo: i32,
+    /// Win chance of the left team, without pity bonus
+    left_win_chance: f64,
+    /// Elo bonus of the left side included in the win chance, when fitted
+    side_advantage: Option<f64>,
+}
+
+struct Analysis {
+    game: GameId,
+    history: Vec<HistoryEntry>,
+    options: SpaweloOptions,
+    model: Box<dyn RatingModel + Send + Sync>,
+}
+
+impl Analysis {
+    fn new(cli: &Cli) -> Result<Self> {
+        let (game, history) = load_history(&cli.history, cli.game.as_deref())?;
+        let options = match &cli.options {
+            Some(path) => load_options(path)?,
+            None => SpaweloOptions::default(),
+        };
+        let kind = cli
+            .model
+            .unwrap_or_else(|| options.rating_model.model_for(&game));
+        info!("Game: {game}, matches: {}, model: {kind}", history.len());
+
+        // Same matches as the app fits its ratings on
+        let mut model = new_rating_model(kind);
+        model.fit(
+            &training_history(&history, &options, Local::now()),
+            &options,
+        );
+        Ok(Analysis {
+            game,
+            history,
+            options,
+            model,
+        })
+    }
+
+    fn with_elo(&self, players: &[String]) -> Vec<PlayerWithElo> {
+        let ratings = self.model.ratings();
+        players
+            .iter()
+            .map(|p| {
+                let id = PlayerId::from(p.as_str());
+                let elo = match ratings.get(&id) {
+                    Some(rating) => rating.round() as i32,
+                    None => {
+                        warn!("No matches of {id} in history, using default elo");
+                        Player::default_elo()
+                    }
+                };
+                PlayerWithElo { id, elo }
+            })
+            .collect()
+    }
+
+    fn lose_streaks(&self, players: &[PlayerWithElo]) -> HashMap<PlayerId, i32> {
+        let max_days = match self.options.pity_bonus.lose_streak_max_days {
+            days if days > 0 => Some(days as u64),
+            _ => None,
+        };
+        let history = History {
+            entries: HashMap::from([(self.game.clone(), self.history.clone())]),
+        };
+        history.calculate_lose_streaks(&self.game, players.iter().map(|p| &p.id), max_days)
+    }
+
+    fn ratings(&self, players: &[String]) -> Vec<Rating> {
+        let mut ratings: Vec<_> = self
+            .model
+            .ratings()
+            .into_iter()
+            .filter(|(p, _)| players.is_empty() || players.iter().any(|q| q == p.as_str()))
+            .map(|(player, rating)| Rating {
+                uncertainty: self.model.uncertainty(&player).map(|u| u.round() as i32),
+                matches: self
+                    .history
+                    .iter()
+                    .filter(|e| !e.fake && e.all_players().any(|p| p == &player))
+                    .count(),
+                rating: rating.round() as i32,
+                player,
+            })
+            .collect();
+        ratings.sort_by(|a, b| b.rating.cmp(&a.rating).then(a.player.cmp(&b.player)));
+        ratings
+    }
+
+    fn predict(&self, left: &[String], right: &[String]) -> Prediction {
+        let left = self.with_elo(left);
+        let right = self.with_elo(right);
+        let lose_streaks = self.lose_streaks(&[left.as_slice(), right.as_slice()].concat());
+        let left_win_chance = self.model.win_probability(&left, &right, Some(Team::Left));
+        let (left, right) = calculate_teams_elo(
+            left,
+            right,
+            &lose_streaks,
+            &self.options,
+            self.model.as_ref(),
+        );
+        Prediction {
+            left: left.players,
+            right: right.players,
+            left_elo: left.real_elo,
+            right_elo: right.real_elo,
+            left_pity_elo: left.pity_elo,
+            right_pity_elo: right.pity_elo,
+            left_win_chance,
+            side_advantage: self.model.side_advantage(),
+        }
+    }
+
+    fn shuffle(&self, players: &[String], seed: u64, temperature: i32) -> Result<Vec<TeamSplit>> {
+        let players = self.with_elo(players);
+        let lose_streaks = self.lose_streaks(&players);
+        Ok(shuffle_teams(
+            players,
+            &lose_streaks,
+            &self.history,
+            temperature,
+            seed,
+            &self.options,
+            self.model.as_ref(),
+        )?)
+    }
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    let cli = Cli::parse();
+    // Written once complete, so a failing command leaves no truncated output file behind
+    let mut out = Vec::new();
+    match &cli.command {
+        Command::Ratings { players } => {
+            let analysis = Analysis::new(&cli)?;
+            let ratings = analysis.ratings(players);
+            match cli.format {
+                Format::Json => write_json(&mut out, &ratings)?,
+                Format::Text => write_ratings(&mut out, &ratings, analysis.model.side_advantage())?,
+            }
+        }
+        Command::Predict { left, right } => {
+            let prediction = Analysis::new(&cli)?.predict(left, right);
+            match cli.format {
+                Format::Json => write_json(&mut out, &prediction)?,
+                Format::Text => write_prediction(&mut out, &prediction)?,
+            }
+        }
+        Command::Shuffle {
+            players,
+            seed,
+            temperature,
+        } => {
+            let splits = Analysis::new(&cli)?.shuffle(players, *seed, *temperature)?;
+            match cli.format {
+                Format::Json => write_json(&mut out, &splits)?,
+                Format::Text => write_splits(&mut out, &splits)?,
+            }
+        }
+        Command::Simulate {
+            option_files,
+            players,
+            lobby_size,
+            matches,
+            skill_spread,
+            refit_interval,
+            seed,
+        } => {
+            let params = SimulationParams {
+                players: *players,
+                lobby_size: *lobby_size,
+                matches: *matches,
+                skill_spread: *skill_spread,
+                refit_interval: *refit_interval,
+                seed: *seed,
+            };
+            let option_sets = if option_files.is_empty() {
+                vec![SpaweloOptions::default()]
+            } else {
+                option_files
+                    .iter()
+                    .map(|path| load_options(path))
+                    .collect::<Result<_>>()?
+            };
+            let reports = simulate(&params, &option_sets)?;
+            match cli.format {
+                Format::Json => write_json(&mut out, &reports)?,
+                Format::Text => write_simulation(&mut out, option_files, &reports)?,
+            }
+        }
+    }
+    match &cli.output {
+        Some(path) => {
+            fs::write(path, out).with_context(|| format!("Cannot write {}", path.display()))?
+        }
+        None => io::stdout().lock().write_all(&out)?,
+    }
+    Ok(())
+}
+
+fn parse_rating_model(value: &str) -> Result<RatingModelKind, String> {
+    match value {
+        "mlElo" => Ok(RatingModelKind::MlElo),
+        "trueSkill" => Ok(RatingModelKind::TrueSkill),
+        other => Err(format!(
+            "unknown model {other}, expected mlElo or trueSkill"
+        )),
+    }
+}
+
+/// Loads history files and returns chronologically sorted entries of the selected game.
+fn load_history(paths: &[PathBuf], game: Option<&str>) -> Result<(GameId, Vec<HistoryEntry>)> {
+    if paths.is_empty() {
+        bail!("No history files given, use --history");
+    }
+    let mut games: HashMap<GameId, Vec<HistoryEntry>> = HashMap::new();
+    for path in paths {
+        let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+        let mut value: serde_json::Value = serde_json::from_reader(file)
+            .with_context(|| format!("Cannot parse {}", path.display()))?;
+        // Upgraded in memory only, the app migrates files when it loads them
+        migration::migrate(&migration::HISTORY, &mut value)
+            .with_context(|| format!("Cannot migrate {}", path.display()))?;
+        let history: HistorySerializeWrapper = serde_json::from_value(value)
+            .with_context(|| format!("Cannot parse {}", path.display()))?;
+        games
+            .entry(history.game)
+            .or_default()
+            .extend(history.entries);
+    }
+    let game = match game {
+        Some(game) => GameId::from(game),
+        None if games.len() == 1 => games.keys().next().unwrap().clone(),
+        None => {
+            let mut names: Vec<_> = games.keys().map(GameId::as_str).collect();
+            names.sort();
+            bail!(
+                "History contains multiple games, select one with --game: {}",
+                names.join(", ")
+            );
+        }
+    };
+    let Some(mut entries) = games.remove(&game) else {
+        bail!("No history of game {game}");
+    };
+    entries.sort_by_key(|e| e.timestamp);
+    Ok((game, entries))
+}
+
+fn load_options(path: &Path) -> Result<SpaweloOptions> {
+    let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    let options: OptionsFile = serde_yaml::from_reader(file)
+        .with_context(|| format!("Cannot parse {}", path.display()))?;
+    Ok(options.spawelo)
+}
+
+fn write_json(out: &mut impl Write, value: &impl Serialize) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_ratings(
+    out: &mut impl Write,
+    ratings: &[Rating],
+    side_advantage: Option<f64>,
+) -> Result<()> {
+    for r in ratings {
+        let uncertainty = r.uncertainty.map(|u| format!(" ±{u}")).unwrap_or_default();
+        writeln!(
+            out,
+            "{:<20} {:>5}{uncertainty} ({} matches)",
+            r.player, r.rating, r.matches
+        )?;
+    }
+    if let Some(advantage) = side_advantage {
+        writeln!(out, "Left side advantage: {advantage:.0}")?;
+    }
+    Ok(())
+}
+
+fn write_prediction(out: &mut impl Write, p: &Prediction) -> Result<()> {
+    writeln!(
+        out,
+        "Left:  {} (elo {}, with pity bonus {})",
+        p.left
+            .iter()
+            .map(PlayerId::as_str)
+            .collect::<Vec<_>>()
+            .join(", "),
+        p.left_elo,
+        p.left_pity_elo
+    )?;
+    writeln!(
+        out,
+        "Right: {} (elo {}, with pity bonus {})",
+        p.right
+            .iter()
+            .map(PlayerId::as_str)
+            .collect::<Vec<_>>()
+            .join(", "),
+        p.right_elo,
+        p.right_pity_elo
+    )?;
+    writeln!(out, "Left win chance: {:.1}%", p.left_win_chance * 100.0)?;
+    if let Some(advantage) = p.side_advantage {
+        writeln!(out, "Left side advantage: {advantage:.0}")?;
+    }
+    Ok(())
+}
+
+fn write_splits(out: &mut impl Write, splits: &[TeamSplit]) -> Result<()> {
+    for (i, split) in splits.iter().enumerate() {
+        writeln!(
+            out,
+            "#{} elo diff {}, left win chance {:.1}%, novelty penalty {}",
+            i + 1,
+            split.elo_diff,
+            split.win_chance * 100.0,
+            split.novelty_penalty
+        )?;
+        for (name, team) in [("Left", &split.left), ("Right", &split.right)] {
+            writeln!(
+                out,
+                "  {name:<5} {} (elo {}, with pity bonus {})",
+                team.players
+                    .iter()
+                    .map(PlayerId::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                team.real_elo,
+                team.pity_elo
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_simulation(
+    out: &mut impl Write,
+    option_files: &[PathBuf],
+    reports: &[SimulationReport],
+) -> Result<()> {
+    for (i, report) in reports.iter().enumerate() {
+        let name = option_files
+            .get(i)
+            .map_or("default options".to_string(), |p| p.display().to_string());
+        writeln!(out, "{name}")?;
+        writeln!(
+            out,
+            "  rating error {:.1}, mean favourite chance {:.1}%, close matches {:.1}%",
+            report.rating_error,
+            report.mean_favourite_chance * 100.0,
+            report.close_matches * 100.0
+        )?;
+        let streaks: Vec<_> = report
+            .lose_streaks
+            .iter()
+            .enumerate()
+            .map(|(length, count)| format!("{}: {count}", length + 1))
+            .collect();
+        writeln!(out, "  lose streaks {}", streaks.join(", "))?;
+    }
+    Ok(())
+}
@@ -1,26 +1,37 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context as _;
 use axum::body::Bytes;
 use axum::extract::ws::{self, WebSocket};
-use axum::extract::{State, WebSocketUpgrade};
+use axum::extract::{Query, State, WebSocketUpgrade};
 use axum::response::{ErrorResponse, IntoResponse, Redirect, Response};
 use axum::routing::{any, get, post};
 use axum::{Json, Router};
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveTime};
+use eloelo_model::hero::{Hero, InvalidHero};
+use eloelo_model::history::{HistoryAmendment, MatchId};
+use eloelo_model::history_query::{HistoryCursor, HistoryQuery};
 use eloelo_model::player::{DiscordUsername, Player};
 use eloelo_model::{GameId, PlayerId, Team, WinScale};
 use futures_util::StreamExt as _;
 use http::{HeaderMap, StatusCode};
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
+use spawelo::{BacktestReport, PlayerRatingHistory};
 use tower_http::services::ServeDir;
 
 use crate::eloelo::message_bus::{
-    Event, FinishMatch, ImageFormat, MatchInfo, Message, MessageBus, UiCommand,
+    Backtest, CorrectionError, Event, FinishMatch, ImageFormat, MatchCorrection, MatchInfo,
+    Message, MessageBus, PairSynergies, PlayerStatsReport, RatingHistoryQuery, Reply, RequestError,
+    ShuffleSelection, StatsPlayer, UiCommand,
 };
+use crate::eloelo::options::EloEloOptions;
 use crate::eloelo::options::EloEloOptionsTransport;
+use crate::eloelo::ui_state::UiHistoryPage;
 use crate::utils::ResultExt as _;
 
 struct AppState {
@@ -29,6 +40,13 @@ struct AppState {
 
 type AppStateArg = State<Arc<AppState>>;
 
+const BACKTEST_DEFAULT_WARMUP: usize = 10;
+const HISTORY_DEFAULT_LIMIT: usize = 50;
+const HISTORY_MAX_LIMIT: usize = 500;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// For requests refitting ratings many times
+const ANALYSIS_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
 #[derive(Serialize)]
 struct EmptyResponse;
 
@@ -44,6 +62,34 @@ fn bad_request(msg: impl Display) -> ErrorResponse {
     (StatusCode::BAD_REQUEST, msg.to_string()).into()
 }
 
+fn correction_failed(e: CorrectionError) -> ErrorResponse {
+    let status = match e {
+        CorrectionError::NotFound(_) => StatusCode::NOT_FOUND,
+        CorrectionError::StoreFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string()).into()
+}
+
+fn request_failed(e: RequestError) -> ErrorResponse {
+    let status = match e {
+        RequestError::Closed => StatusCode::SERVICE_UNAVAILABLE,
+        RequestError::TimedOut => StatusCode::GATEWAY_TIMEOUT,
+    };
+    (status, e.to_string()).into()
+}
+
+/// Fails with the date when its midnight does not exist in local time zone.
+fn start_of_day(date: NaiveDate) -> Result<DateTime<Local>, NaiveDate> {
+    date.and_time(NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or(date)
+}
+
+fn invalid_date(date: NaiveDate) -> ErrorResponse {
+    bad_request(format!("Invalid local date: {date}"))
+}
+
 async fn initialize_ui(State(state): AppStateArg) -> impl IntoResponse {
     debug!("initialize_ui");
     let _ = state
@@ -155,11 +201,20 @@ async fn start_match(State(state): AppStateArg) -> impl IntoResponse {
     EmptyResponse
 }
 
-async fn shuffle_teams(State(state): AppStateArg) -> impl IntoResponse {
-    debug!("shuffle_teams()");
+#[derive(Debug, Deserialize)]
+struct ShuffleTeamsBody {
+    /// Replays a shuffle with the given seed, a fresh seed is drawn when missing
+    seed: Option<u64>,
+}
+
+async fn shuffle_teams(
+    State(state): AppStateArg,
+    Json(body): Json<ShuffleTeamsBody>,
+) -> impl IntoResponse {
+    debug!("shuffle_teams({body:?})");
     let _ = state
         .message_bus
-        .send(Message::UiCommand(UiCommand::ShuffleTeams));
+        .send(Message::UiCommand(UiCommand::ShuffleTeams(body.seed)));
     EmptyResponse
 }
 
@@ -196,6 +251,9 @@ struct FinishMatchBody {
     scale: Option<WinScale>,
     duration: Option<std::time::Duration>, //TODO: check if we can send Duration
     fake: Option<bool>,
+    /// Heroes played, by player id
+    #[serde(default)]
+    heroes: HashMap<PlayerId, String>,
 }
 async fn finish_match(
     State(state): AppStateArg,
@@ -210,11 +268,13 @@ async fn finish_match(
                 .ok_or_else(|| bad_request("Missing match duration"))?;
             let scale = body.scale.ok_or_else(|| bad_request("Missing win scale"))?;
             let fake = body.fake.unwrap_or(false);
+            let heroes = parse_heroes(body.heroes).map_err(bad_request)?;
             UiCommand::FinishMatch(FinishMatch::Finished(MatchInfo {
                 winner,
                 scale,
                 duration,
                 fake,
+                heroes,
             }))
         }
     };
@@ -222,6 +282,107 @@ async fn finish_match(
     Ok(EmptyResponse)
 }
 
+#[derive(Debug, Deserialize)]
+struct AmendMatchBody {
+    id: MatchId,
+    author: String,
+    reason: String,
+    /// Swaps winner and loser of the match
+    #[serde(default)]
+    swap_winner: bool,
+    scale: Option<WinScale>,
+    duration: Option<std::time::Duration>,
+    fake: Option<bool>,
+    #[serde(default)]
+    heroes: HashMap<PlayerId, String>,
+}
+async fn amend_match(
+    State(state): AppStateArg,
+    Json(body): Json<AmendMatchBody>,
+) -> axum::response::Result<impl IntoResponse> {
+    debug!("amend_match({body:?})");
+    let heroes = parse_heroes(body.heroes).map_err(bad_request)?;
+    let correction = correction(body.id, body.author, body.reason).map_err(bad_request)?;
+    request_correction(
+        &state.message_bus,
+        MatchCorrection {
+            amendment: Some(HistoryAmendment {
+                swap_winner: body.swap_winner,
+                scale: body.scale,
+                duration: body.duration,
+                fake: body.fake,
+                heroes,
+            }),
+            ..correction
+        },
+    )
+    .await?;
+    Ok(EmptyResponse)
+}
+
+#[derive(Debug, Deserialize)]
+struct VoidMatchBody {
+    id: MatchId,
+    author: String,
+    reason: String,
+}
+async fn void_match(
+    State(state): AppStateArg,
+    Json(body): Json<VoidMatchBody>,
+) -> axum::response::Result<impl IntoResponse> {
+    debug!("void_match({body:?})");
+    let correction = correction(body.id, body.author, body.reason).map_err(bad_request)?;
+    request_correction(&state.message_bus, correction).await?;
+    Ok(EmptyResponse)
+}
+
+async fn request_correction(
+    message_bus: &MessageBus,
+    correction: MatchCorrection,
+) -> axum::response::Result<()> {
+    message_bus
+        .request(
+            |request_id| UiCommand::CorrectMatch {
+                request_id,
+                correction,
+            },
+            |reply| match reply {
+                Reply::MatchCorrected(result) => Some(result),
+                _ => None,
+            },
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map_err(request_failed)?
+        .map_err(correction_failed)
+}
+
+fn correction(
+    id: MatchId,
+    author: String,
+    reason: String,
+) -> Result<MatchCorrection, &'static str> {
+    if author.trim().is_empty() {
+        return Err("Missing author");
+    }
+    if reason.trim().is_empty() {
+        return Err("Missing reason");
+    }
+    Ok(MatchCorrection {
+        id,
+        author,
+        reason,
+        amendment: None,
+    })
+}
+
+fn parse_heroes(heroes: HashMap<PlayerId, String>) -> Result<HashMap<PlayerId, Hero>, InvalidHero> {
+    heroes
+        .into_iter()
+        .map(|(player, hero)| Ok((player, Hero::try_from(hero)?)))
+        .collect()
+}
+
 async fn call_to_lobby(State(state): AppStateArg) -> impl IntoResponse {
     state
         .message_bus
@@ -267,6 +428,264 @@ async fn save_options(
     EmptyResponse
 }
 
+#[derive(Debug, Deserialize)]
+struct BacktestBody {
+    options: Option<EloEloOptionsTransport>,
+    warmup: Option<usize>,
+}
+
+async fn backtest(
+    State(state): AppStateArg,
+    Json(body): Json<BacktestBody>,
+) -> axum::response::Result<Json<BacktestReport>> {
+    debug!("backtest({body:?})");
+    let options = body.options.map(|o| {
+        let options: EloEloOptions = o.into();
+        options.spawelo
+    });
+    let backtest = Backtest {
+        options,
+        warmup: body.warmup.unwrap_or(BACKTEST_DEFAULT_WARMUP),
+    };
+    let report = state
+        .message_bus
+        .request(
+            |request_id| UiCommand::Backtest {
+                request_id,
+                backtest,
+            },
+            |reply| match reply {
+                Reply::Backtest(report) => Some(report),
+                _ => None,
+            },
+            ANALYSIS_TIMEOUT,
+        )
+        .await
+        .map_err(request_failed)?;
+    Ok(Json(report))
+}
+
+#[derive(Debug, Deserialize)]
+struct TuneOptionsBody {
+    warmup: Option<usize>,
+}
+
+async fn tune_options(
+    State(state): AppStateArg,
+    Json(body): Json<TuneOptionsBody>,
+) -> impl IntoResponse {
+    debug!("tune_options({body:?})");
+    state
+        .message_bus
+        .send(Message::UiCommand(UiCommand::TuneOptions {
+            warmup: body.warmup.unwrap_or(BACKTEST_DEFAULT_WARMUP),
+        }));
+    EmptyResponse
+}
+
+async fn apply_tuned_options(State(state): AppStateArg) -> impl IntoResponse {
+    debug!("apply_tuned_options()");
+    state
+        .message_bus
+        .send(Message::UiCommand(UiCommand::ApplyTunedOptions));
+    EmptyResponse
+}
+
+#[derive(Debug, Deserialize)]
+struct PairSynergiesBody {
+    limit: Option<usize>,
+}
+
+async fn pair_synergies(
+    State(state): AppStateArg,
+    Json(body): Json<PairSynergiesBody>,
+) -> axum::response::Result<Json<PairSynergies>> {
+    debug!("pair_synergies({body:?})");
+    let limit = body.limit.unwrap_or(10);
+    let synergies = state
+        .message_bus
+        .request(
+            |request_id| UiCommand::ListPairSynergies { request_id, limit },
+            |reply| match reply {
+                Reply::PairSynergies(synergies) => Some(synergies),
+                _ => None,
+            },
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map_err(request_failed)?;
+    Ok(Json(synergies))
+}
+
+#[derive(Debug, Deserialize)]
+struct SelectShuffleBody {
+    /// Index of the alternative to pick, cycles to the next one when missing
+    index: Option<usize>,
+    #[serde(default)]
+    previous: bool,
+}
+
+async fn select_shuffle(
+    State(state): AppStateArg,
+    Json(body): Json<SelectShuffleBody>,
+) -> impl IntoResponse {
+    debug!("select_shuffle({body:?})");
+    let selection = match body {
+        SelectShuffleBody {
+            index: Some(index), ..
+        } => ShuffleSelection::Index(index),
+        SelectShuffleBody { previous: true, .. } => ShuffleSelection::Previous,
+        SelectShuffleBody { .. } => ShuffleSelection::Next,
+    };
+    state
+        .message_bus
+        .send(Message::UiCommand(UiCommand::SelectShuffle(selection)));
+    EmptyResponse
+}
+
+#[derive(Debug, Deserialize)]
+struct RatingHistoryBody {
+    #[serde(default)]
+    players: Vec<PlayerId>,
+    /// First day of the range, inclusive
+    from: Option<NaiveDate>,
+    /// Last day of the range, inclusive
+    to: Option<NaiveDate>,
+}
+
+async fn rating_history(
+    State(state): AppStateArg,
+    Json(body): Json<RatingHistoryBody>,
+) -> axum::response::Result<Json<Vec<PlayerRatingHistory>>> {
+    debug!("rating_history({body:?})");
+    let from = body
+        .from
+        .map(start_of_day)
+        .transpose()
+        .map_err(invalid_date)?;
+    let to = body
+        .to
+        .map(|to| start_of_day(to + Days::new(1)))
+        .transpose()
+        .map_err(invalid_date)?;
+    let query = RatingHistoryQuery {
+        players: body.players,
+        from,
+        to,
+    };
+    let players = state
+        .message_bus
+        .request(
+            |request_id| UiCommand::RatingHistory { request_id, query },
+            |reply| match reply {
+                Reply::RatingHistory(players) => Some(players),
+                _ => None,
+            },
+            ANALYSIS_TIMEOUT,
+        )
+        .await
+        .map_err(request_failed)?;
+    Ok(Json(players))
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryParams {
+    game: GameId,
+    player: Option<PlayerId>,
+    /// First day of the range, inclusive
+    from: Option<NaiveDate>,
+    /// Last day of the range, inclusive
+    to: Option<NaiveDate>,
+    fake: Option<bool>,
+    /// Includes fake matches that no longer count for ratings
+    #[serde(default)]
+    outdated_fakes: bool,
+    scale: Option<WinScale>,
+    /// `nextCursor` of the previous page
+    cursor: Option<HistoryCursor>,
+    limit: Option<usize>,
+}
+
+async fn history(
+    State(state): AppStateArg,
+    Query(params): Query<HistoryParams>,
+) -> axum::response::Result<Json<UiHistoryPage>> {
+    debug!("history({params:?})");
+    let from = params
+        .from
+        .map(start_of_day)
+        .transpose()
+        .map_err(invalid_date)?;
+    let to = params
+        .to
+        .map(|to| start_of_day(to + Days::new(1)))
+        .transpose()
+        .map_err(invalid_date)?;
+    let limit = params.limit.unwrap_or(HISTORY_DEFAULT_LIMIT);
+    if !(1..=HISTORY_MAX_LIMIT).contains(&limit) {
+        return Err(bad_request(format!(
+            "Limit must be between 1 and {HISTORY_MAX_LIMIT}"
+        )));
+    }
+    let query = HistoryQuery {
+        game: params.game,
+        player: params.player,
+        from,
+        to,
+        fake: params.fake,
+        fakes_since: None,
+        scale: params.scale,
+        cursor: params.cursor,
+        limit,
+    };
+    let outdated_fakes = params.outdated_fakes;
+
+    let page = state
+        .message_bus
+        .request(
+            |request_id| UiCommand::QueryHistory {
+                request_id,
+                query,
+                outdated_fakes,
+            },
+            |reply| match reply {
+                Reply::HistoryPage(page) => Some(page),
+                _ => None,
+            },
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map_err(request_failed)?;
+    Ok(Json(page))
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayerStatsBody {
+    /// All players when missing
+    player: Option<PlayerId>,
+}
+
+async fn player_stats(
+    State(state): AppStateArg,
+    Json(body): Json<PlayerStatsBody>,
+) -> axum::response::Result<Json<PlayerStatsReport>> {
+    debug!("player_stats({body:?})");
+    let player = body.player.map_or(StatsPlayer::All, StatsPlayer::Id);
+    let report = state
+        .message_bus
+        .request(
+            |request_id| UiCommand::PlayerStats { request_id, player },
+            |reply| match reply {
+                Reply::PlayerStats(report) => Some(report),
+                _ => None,
+            },
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map_err(request_failed)?;
+    Ok(Json(report))
+}
+
 #[derive(Serialize, Deserialize)]
 struct ShuffleTemperatureBody {
     value: i32,
@@ -369,7 +788,10 @@ pub async fn serve(message_bus: MessageBus, static_serving_dir: PathBuf, addr: S
                 .route("/change_game", post(change_game))
                 .route("/start_match", post(start_match))
                 .route("/finish_match", post(finish_match))
+                .route("/amend_match", post(amend_match))
+                .route("/void_match", post(void_match))
                 .route("/shuffle_teams", post(shuffle_teams))
+                .route("/select_shuffle", post(select_shuffle))
                 .route("/refresh_elo", post(refresh_elo))
                 .route("/call_to_lobby", post(call_to_lobby))
                 .route("/present_in_lobby_change", post(present_in_lobby_change))
@@ -377,6 +799,13 @@ pub async fn serve(message_bus: MessageBus, static_serving_dir: PathBuf, addr: S
                 .route("/fill_lobby", post(fill_lobby))
                 .route("/call_player", post(call_player))
                 .route("/options", post(save_options))
+                .route("/backtest", post(backtest))
+                .route("/tune_options", post(tune_options))
+                .route("/apply_tuned_options", post(apply_tuned_options))
+                .route("/pair_synergies", post(pair_synergies))
+                .route("/rating_history", post(rating_history))
+                .route("/player_stats", post(player_stats))
+                .route("/history", get(history))
                 .route("/shuffle_temperature", post(set_shuffle_temperature)),
         )
         .route("/api/v1/dota_screenshot", post(process_dota_screenshot))
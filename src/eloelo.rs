@@ -4,24 +4,28 @@ use std::time::Duration;
 use crate::eloelo::fosiaudio::FosiaudioClient;
 use crate::eloelo::message_bus::MatchInfo;
 use crate::eloelo::options::EloEloOptions;
-use crate::eloelo::ui_state::{MatchMetadata, UiHistory, UiHistoryEntry};
+use crate::eloelo::ui_state::{MatchMetadata, UiHistoryEntry, UiHistoryPage};
 use crate::utils::{duration_minutes, print_err, unwrap_or_def_verbose, ResultExt as _};
 use anyhow::{Context, Result};
 use chrono::Local;
 use config::Config;
 use eloelo_model::decimal::Decimal;
-use eloelo_model::history::{History, HistoryEntry};
-use eloelo_model::player::{Player, PlayerDb, PlayersConfig};
+use eloelo_model::hero::Hero;
+use eloelo_model::history::{History, HistoryCorrection, HistoryEntry, MatchId};
+use eloelo_model::history_query::HistoryQuery;
+use eloelo_model::player::{DiscordUsername, Player, PlayerDb, PlayersConfig};
 use eloelo_model::{BalancedTeam, GameId, GameState, PlayerId, Team, WinScale};
 use futures_util::stream::{StreamExt as _, TryStreamExt as _};
 use git_mirror::GitMirror;
 use log::{debug, error, info, warn};
 use message_bus::{
-    Event, FinishMatch, MatchStart, MatchStartTeam, Message, MessageBus, RichMatchResult, UiCommand,
+    Backtest, CorrectionError, Event, FinishMatch, MatchCorrection, MatchStart, MatchStartTeam,
+    Message, MessageBus, PairSynergies, PlayerStatsReport, RatingHistoryQuery, Reply, RequestId,
+    RichMatchResult, ShuffleSelection, StatsPlayer, UiCommand, UiUpdate,
 };
 use regex::Regex;
-use spawelo::ml_elo;
-use ui_state::{PityBonus, State, UiPlayer, UiState};
+use spawelo::{MlEloOptions, RatingModel, TeamSplit, TuningResult};
+use ui_state::{new_seed, MatchHeroes, PityBonus, State, UiPlayer, UiState};
 
 mod config;
 pub(crate) mod elodisco;
@@ -31,7 +35,7 @@ pub(crate) mod message_bus;
 pub(crate) mod ocr;
 pub mod options;
 pub(crate) mod store;
-mod ui_state;
+pub(crate) mod ui_state;
 
 pub struct EloElo {
     selected_game: GameId,
@@ -47,6 +51,15 @@ pub struct EloElo {
     git_mirror: GitMirror,
     options: EloEloOptions,
     shuffle_temperature: i32,
+    shuffle_error: Option<String>,
+    shuffle_alternatives: Vec<TeamSplit>,
+    selected_shuffle: usize,
+    /// Seed of the last shuffle, also used for hero assignment of the next match
+    seed: u64,
+    match_heroes: MatchHeroes,
+    rating_model: Box<dyn RatingModel + Send + Sync>,
+    tuning_result: Option<TuningResult>,
+    history_revision: u64,
 }
 
 impl EloElo {
@@ -54,10 +67,16 @@ impl EloElo {
         state: Option<State>,
         config: Config,
         players_config: PlayersConfig,
-        options: EloEloOptions,
+        mut options: EloEloOptions,
         message_bus: MessageBus,
     ) -> Self {
         let state = state.unwrap_or_else(|| State::new(config.default_game().clone()));
+        options
+            .spawelo
+            .rating_model
+            .ensure_games(config.games.iter().map(|g| &g.name));
+        let rating_model =
+            spawelo::new_rating_model(options.spawelo.rating_model.model_for(&state.selected_game));
 
         let _ = std::fs::create_dir_all(&config.history_git_mirror)
             .inspect_err(|e| error!("Cannot create git mirror directory - {e}"));
@@ -82,12 +101,32 @@ impl EloElo {
             git_mirror,
             options,
             shuffle_temperature: state.shuffle_temperature,
+            shuffle_error: None,
+            shuffle_alternatives: Vec::new(),
+            selected_shuffle: 0,
+            seed: state.seed,
+            match_heroes: state.match_heroes,
+            rating_model,
+            tuning_result: None,
+            history_revision: 0,
         };
         elo.recalculate_elo_from_history();
         elo
     }
 
     pub async fn dispatch_ui_command(&mut self, ui_command: UiCommand) {
+        // The error refers to the teams of the last shuffle
+        if matches!(
+            ui_command,
+            UiCommand::ShuffleTeams(_)
+                | UiCommand::SelectShuffle(_)
+                | UiCommand::MovePlayerToOtherTeam(_)
+                | UiCommand::RemovePlayerFromTeam(_)
+                | UiCommand::AddPlayerToTeam(..)
+                | UiCommand::ChangeGame(_)
+        ) {
+            self.shuffle_error = None;
+        }
         match ui_command {
             UiCommand::InitializeUi => {}
             UiCommand::AddNewPlayer(player) => self.add_new_player(player),
@@ -110,11 +149,37 @@ impl EloElo {
             UiCommand::FillLobby => self.fill_lobby().await,
             UiCommand::ClearLobby => self.clear_lobby(),
             UiCommand::CallPlayer(player_id) => self.call_player(&player_id).await,
-            UiCommand::ShuffleTeams => self.shuffle_teams(),
+            UiCommand::ShuffleTeams(seed) => self.shuffle_teams(seed),
             UiCommand::RefreshElo => self.recalculate_elo_from_history(),
             UiCommand::FinishMatch(finish_match) => self.finish_match(finish_match).await,
             UiCommand::UpdateOptions(options) => self.update_options(options),
             UiCommand::SetShuffleTemperature(temperature) => self.shuffle_temperature = temperature,
+            UiCommand::SelectShuffle(selection) => self.select_shuffle(selection),
+            UiCommand::Backtest {
+                request_id,
+                backtest,
+            } => self.backtest(request_id, backtest),
+            UiCommand::TuneOptions { warmup } => self.tune_options(warmup),
+            UiCommand::TuningFinished(result) => self.tuning_finished(result),
+            UiCommand::ApplyTunedOptions => self.apply_tuned_options(),
+            UiCommand::ListPairSynergies { request_id, limit } => {
+                self.list_pair_synergies(request_id, limit)
+            }
+            UiCommand::RatingHistory { request_id, query } => {
+                self.rating_history(request_id, query)
+            }
+            UiCommand::PlayerStats { request_id, player } => self.player_stats(request_id, player),
+            UiCommand::OfferedHeroes(offered) => self.add_offered_heroes(offered),
+            UiCommand::SetPlayedHero { player, hero } => self.set_played_hero(&player, hero),
+            UiCommand::CorrectMatch {
+                request_id,
+                correction,
+            } => self.correct_match(request_id, correction),
+            UiCommand::QueryHistory {
+                request_id,
+                query,
+                outdated_fakes,
+            } => self.query_history(request_id, query, outdated_fakes),
             UiCommand::CloseApplication => {
                 if let Err(e) = self.store_state() {
                     error!("store_state failed: {}", e);
@@ -156,6 +221,8 @@ impl EloElo {
             game_state: self.game_state,
             lobby: self.lobby.clone(),
             shuffle_temperature: self.shuffle_temperature,
+            seed: self.seed,
+            match_heroes: self.match_heroes.clone(),
         };
         store::store_state(&state)?;
         store::store_options(&self.options)?;
@@ -173,19 +240,38 @@ impl EloElo {
             reserve_players: self.build_ui_players(reserve_players, default_elo),
             pity_bonus: self.make_pity_bonus_data(&self.left_team, &self.right_team),
             game_state: self.game_state,
-            history: self.build_ui_history(),
             options: self.options.to_described_options_group_vec(),
-            win_prediction: Decimal::with_precision(
-                spawelo::calculate_win_prediction(
-                    self.left_team.real_elo,
-                    self.right_team.real_elo,
-                ),
-                3,
-            ),
+            win_prediction: Decimal::with_precision(self.win_prediction(default_elo), 3),
             shuffle_temperature: self.shuffle_temperature,
+            shuffle_error: self.shuffle_error.clone(),
+            shuffle_alternatives: self.current_shuffle_alternatives().to_vec(),
+            selected_shuffle: if self.current_shuffle_alternatives().is_empty() {
+                None
+            } else {
+                Some(self.selected_shuffle)
+            },
+            seed: self.seed,
+            side_advantage: self
+                .rating_model
+                .side_advantage()
+                .map(|advantage| advantage.round() as i32),
+            history_revision: self.history_revision,
         }
     }
 
+    fn win_prediction(&self, default_elo: i32) -> f64 {
+        let left: Vec<_> = self
+            .players
+            .get_ranked_owned(&self.left_team.players, &self.selected_game, default_elo)
+            .collect();
+        let right: Vec<_> = self
+            .players
+            .get_ranked_owned(&self.right_team.players, &self.selected_game, default_elo)
+            .collect();
+        self.rating_model
+            .win_probability(&left, &right, Some(Team::Left))
+    }
+
     fn players_in_team(&self) -> impl Iterator<Item = &PlayerId> {
         self.players.all().filter_map(|p| {
             if !self.is_in_a_team(&p.id) {
@@ -251,6 +337,10 @@ impl EloElo {
                     .and_then(|p| p.discord_username().map(|n| n.to_string()));
                 let present_in_lobby = self.lobby.contains(&player);
                 let lose_streak = lose_streaks.get(&player).copied();
+                let uncertainty = self
+                    .rating_model
+                    .uncertainty(&player)
+                    .map(|u| u.round() as i32);
                 UiPlayer {
                     id: player,
                     name,
@@ -258,6 +348,7 @@ impl EloElo {
                     elo,
                     present_in_lobby,
                     lose_streak,
+                    uncertainty,
                 }
             })
             .collect()
@@ -305,6 +396,7 @@ impl EloElo {
             right,
             &&self.lose_streaks_for_current_lobby(),
             &self.options.spawelo,
+            self.rating_model.as_ref(),
         );
     }
 
@@ -325,12 +417,25 @@ impl EloElo {
 
     fn change_game(&mut self, game: GameId) {
         self.selected_game = game;
+        self.rebuild_rating_model();
         self.recalculate_elo_from_history();
     }
 
+    fn rebuild_rating_model(&mut self) {
+        let kind = self
+            .options
+            .spawelo
+            .rating_model
+            .model_for(&self.selected_game);
+        // Fresh model also drops warm-start state fitted for another game.
+        info!("Rating model for {}: {kind}", self.selected_game);
+        self.rating_model = spawelo::new_rating_model(kind);
+    }
+
     fn start_match(&mut self) {
         let default_elo = self.default_elo_for_current_game();
         self.game_state = GameState::MatchInProgress;
+        self.match_heroes = MatchHeroes::default();
         self.message_bus
             .send(Message::Event(Event::MatchStart(MatchStart {
                 game: self.selected_game.clone(),
@@ -347,6 +452,7 @@ impl EloElo {
                         .get_ranked_owned(&self.left_team.players, &self.selected_game, default_elo)
                         .map(|p| (p.id, p.elo))
                         .collect(),
+                    uncertainty: self.players_uncertainty(&self.left_team.players),
                 },
                 right_team: MatchStartTeam {
                     name: self
@@ -364,16 +470,29 @@ impl EloElo {
                         )
                         .map(|p| (p.id, p.elo))
                         .collect(),
+                    uncertainty: self.players_uncertainty(&self.right_team.players),
                 },
+                seed: self.seed,
             })));
     }
 
+    fn players_uncertainty(&self, players: &[PlayerId]) -> HashMap<PlayerId, i32> {
+        players
+            .iter()
+            .filter_map(|p| {
+                self.rating_model
+                    .uncertainty(p)
+                    .map(|u| (p.clone(), u.round() as i32))
+            })
+            .collect()
+    }
+
     async fn finish_match(&mut self, finish_match: FinishMatch) {
         if let FinishMatch::Finished(info) = finish_match {
-            let history_entry = self.make_history_entry(info);
+            let history_entry = self.make_history_entry(&info);
             self.store_updated_history(&history_entry, info.winner);
             self.play_winner_theme(info.winner).await;
-            self.send_match_result(info);
+            self.send_match_result(&info);
 
             // Failsafe history message in log
             let history_log_msg = serde_json::to_string(&history_entry)
@@ -384,16 +503,107 @@ impl EloElo {
             self.history_for_current_game_mut().push(history_entry);
             self.update_elo();
             self.lobby = HashSet::new();
+            self.seed = new_seed();
         }
 
         self.game_state = GameState::AssemblingTeams;
+        self.match_heroes = MatchHeroes::default();
         debug!("finish_match handled");
     }
 
+    fn add_offered_heroes(&mut self, offered: HashMap<DiscordUsername, Vec<Hero>>) {
+        if self.game_state != GameState::MatchInProgress {
+            warn!("Ignoring offered heroes, no match in progress");
+            return;
+        }
+        for (username, heroes) in offered {
+            let player = self.player_id_for_discord(&username);
+            self.match_heroes
+                .offered
+                .entry(player)
+                .or_default()
+                .extend(heroes);
+        }
+    }
+
+    fn set_played_hero(&mut self, username: &DiscordUsername, hero: Hero) {
+        if self.game_state != GameState::MatchInProgress {
+            warn!("Ignoring {hero} played by {username}, no match in progress");
+            return;
+        }
+        let player = self.player_id_for_discord(username);
+        info!("{player} plays {hero}");
+        self.match_heroes.played.insert(player, hero);
+    }
+
+    fn correct_match(&mut self, request_id: RequestId, correction: MatchCorrection) {
+        let result = self.apply_correction(correction);
+        if let Err(e) = &result {
+            warn!("Cannot correct match: {e}");
+        }
+        self.message_bus
+            .send(Message::Reply(request_id, Reply::MatchCorrected(result)));
+    }
+
+    fn apply_correction(&mut self, correction: MatchCorrection) -> Result<(), CorrectionError> {
+        let (game, index) = find_match(&self.history, &correction.id)?;
+        let mut entries = self.history.entries[&game].clone();
+        let previous = entries[index].clone();
+        let current = correction
+            .amendment
+            .as_ref()
+            .map(|amendment| previous.amended(amendment));
+        match &current {
+            Some(entry) => entries[index] = entry.clone(),
+            None => {
+                entries.remove(index);
+            }
+        }
+        let record = HistoryCorrection {
+            match_id: correction.id,
+            timestamp: Local::now(),
+            author: correction.author,
+            reason: correction.reason,
+            previous,
+            current,
+        };
+        let record_log_msg = serde_json::to_string(&record)
+            .unwrap_or_else(|e| format!("Failed to serialize correction: {e}"));
+        info!(target: "history", "CorrectMatch: {record_log_msg}");
+
+        store::store_history_entries(&game, entries)
+            .and_then(|_| store::append_history_correction(&game, &record))
+            .map_err(|e| CorrectionError::StoreFailed(format!("{e:#}")))?;
+        if !self.config.test_mode {
+            self.git_mirror
+                .sync(Some(&mk_correction_commit_message(&game, &record)))
+                .context("Failed to sync history git mirror")
+                .print_err();
+        }
+        self.recalculate_elo_from_history();
+        Ok(())
+    }
+
+    fn player_id_for_discord(&self, username: &DiscordUsername) -> PlayerId {
+        self.players
+            .all()
+            .find(|p| p.discord_username() == Some(username))
+            .map_or_else(|| PlayerId::from(username.as_str()), |p| p.id.clone())
+    }
+
     fn update_options(&mut self, options: EloEloOptions) {
         info!("Update options: {:?}", options);
+        let (old, new) = (&self.options.spawelo, &options.spawelo);
+        let model_changed =
+            new.rating_model.model_for(&self.selected_game) != self.rating_model.kind();
+        // Ratings fitted with previous options no longer apply
+        let fit_changed = new.ml_elo != old.ml_elo || new.true_skill != old.true_skill;
         self.options = options;
         store::store_options(&self.options).print_err();
+        if model_changed || fit_changed {
+            self.rebuild_rating_model();
+            self.recalculate_elo_from_history();
+        }
     }
 
     fn mk_finish_match_commit_message(
@@ -430,17 +640,24 @@ impl EloElo {
     }
 
     fn update_elo(&mut self) {
-        let updates = ml_elo(
+        self.rating_model.fit(
             &self.history_for_elo_calc(&self.selected_game),
-            &self.options.spawelo.ml_elo,
+            &self.options.spawelo,
         );
-        for (player, new_elo) in updates.iter() {
+        for (player, new_elo) in self.rating_model.ratings().iter() {
             self.players
                 .set_rank(player, &self.selected_game, *new_elo as i32);
         }
+        if let Some(advantage) = self.rating_model.side_advantage() {
+            info!(
+                "{} left side advantage: {advantage:.0} elo",
+                self.selected_game
+            );
+        }
     }
 
     fn history_for_current_game_mut(&mut self) -> &mut Vec<HistoryEntry> {
+        self.history_revision += 1;
         self.history
             .entries
             .entry(self.selected_game.clone())
@@ -448,49 +665,135 @@ impl EloElo {
     }
 
     fn history_for_elo_calc(&self, game: &GameId) -> Vec<HistoryEntry> {
-        let n = if self.options.spawelo.ml_elo.max_elo_history <= 0 {
-            usize::MAX
-        } else {
-            self.options.spawelo.ml_elo.max_elo_history as usize
-        };
-        let not_fake_or_recent_enough = {
-            let fake_deadline = if self.options.spawelo.ml_elo.fake_match_max_days > 0 {
-                Local::now()
-                    - Duration::from_secs(self.options.spawelo.ml_elo.fake_match_max_days as u64)
-            } else {
-                // Make sure all fake matches will be outdated on invalid max_days
-                Local::now() + Duration::from_secs(3600)
-            };
-            move |e: &&HistoryEntry| {
-                if !e.fake {
-                    return true;
-                }
-                e.timestamp >= fake_deadline
-            }
-        };
+        let history = spawelo::training_history(
+            self.history.entries.get(game).into_iter().flatten(),
+            &self.options.spawelo,
+            Local::now(),
+        );
+        if history.is_empty() {
+            warn!("No history entries");
+        }
+        history
+    }
+
+    fn history_without_outdated_fakes(
+        &self,
+        game: &GameId,
+        options: &MlEloOptions,
+    ) -> Vec<HistoryEntry> {
+        spawelo::without_outdated_fakes(
+            self.history.entries.get(game).into_iter().flatten(),
+            options,
+            Local::now(),
+        )
+    }
+
+    fn backtest(&self, request_id: RequestId, backtest: Backtest) {
+        let options = backtest
+            .options
+            .unwrap_or_else(|| self.options.spawelo.clone());
+        let kind = options.rating_model.model_for(&self.selected_game);
+        info!("Backtesting {} with {kind} model", self.selected_game);
+        let history = self.history_without_outdated_fakes(&self.selected_game, &options.ml_elo);
+        let message_bus = self.message_bus.clone();
+        tokio::task::spawn_blocking(move || {
+            let report = spawelo::backtest(&history, &options, kind, backtest.warmup);
+            message_bus.send(Message::Reply(request_id, Reply::Backtest(report)));
+        });
+    }
+
+    fn tune_options(&self, warmup: usize) {
+        info!("Tuning ML ELO options on {} history", self.selected_game);
+        let options = self.options.spawelo.clone();
+        let history = self.history_without_outdated_fakes(&self.selected_game, &options.ml_elo);
+        let message_bus = self.message_bus.clone();
+        tokio::task::spawn_blocking(move || {
+            let result = spawelo::tune_ml_elo(&history, &options, warmup);
+            message_bus.send(Message::UiCommand(UiCommand::TuningFinished(result)));
+        });
+    }
+
+    fn tuning_finished(&mut self, result: TuningResult) {
+        self.message_bus
+            .send(Message::UiUpdate(UiUpdate::Tuning(result.clone())));
+        self.tuning_result = Some(result);
+    }
+
+    fn list_pair_synergies(&self, request_id: RequestId, limit: usize) {
+        let mut synergies = self.rating_model.synergies();
+        if synergies.is_empty() {
+            warn!("No pair synergies, enable them in ML ELO options");
+        }
+        synergies.sort_by(|a, b| b.synergy.total_cmp(&a.synergy));
+        let strongest = synergies.iter().take(limit).cloned().collect();
+        let weakest = synergies.iter().rev().take(limit).cloned().collect();
+        self.message_bus.send(Message::Reply(
+            request_id,
+            Reply::PairSynergies(PairSynergies {
+                game: self.selected_game.clone(),
+                strongest,
+                weakest,
+            }),
+        ));
+    }
+
+    fn rating_history(&self, request_id: RequestId, query: RatingHistoryQuery) {
+        let options = self.options.spawelo.clone();
+        let kind = options.rating_model.model_for(&self.selected_game);
+        let history = self.history_without_outdated_fakes(&self.selected_game, &options.ml_elo);
+        let message_bus = self.message_bus.clone();
+        tokio::task::spawn_blocking(move || {
+            let players = spawelo::rating_history(
+                &history,
+                &options,
+                kind,
+                &query.players,
+                query.from,
+                query.to,
+            );
+            message_bus.send(Message::Reply(request_id, Reply::RatingHistory(players)));
+        });
+    }
 
-        let history: Vec<HistoryEntry> = self
+    fn player_stats(&self, request_id: RequestId, player: StatsPlayer) {
+        let player = match player {
+            StatsPlayer::All => None,
+            StatsPlayer::Id(id) => Some(id),
+            StatsPlayer::Discord(username) => Some(self.player_id_for_discord(&username)),
+        };
+        let history = self
             .history
             .entries
-            .get(game)
+            .get(&self.selected_game)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let players = eloelo_model::stats::player_stats(history)
             .into_iter()
-            .flatten()
-            .filter(not_fake_or_recent_enough)
-            .rev()
-            .take(n)
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev()
-            .cloned()
+            .filter(|stats| player.as_ref().is_none_or(|p| *p == stats.player))
             .collect();
+        self.message_bus.send(Message::Reply(
+            request_id,
+            Reply::PlayerStats(PlayerStatsReport {
+                game: self.selected_game.clone(),
+                players,
+            }),
+        ));
+    }
 
-        if history.len() == 0 {
-            warn!("No history entries");
-        }
-        history
+    fn apply_tuned_options(&mut self) {
+        let Some(result) = self.tuning_result.take() else {
+            warn!("No tuning result to apply");
+            return;
+        };
+        let mut options = self.options.clone();
+        options.spawelo.ml_elo = result.options;
+        self.update_options(options);
+        self.recalculate_elo_from_history();
     }
 
-    fn shuffle_teams(&mut self) {
+    fn shuffle_teams(&mut self, seed: Option<u64>) {
+        self.seed = seed.unwrap_or_else(new_seed);
+        info!("Shuffling teams with seed {}", self.seed);
         let default_elo = self.default_elo_for_current_game();
         let left = self.players.get_ranked_owned(
             &self.left_team.players,
@@ -503,15 +806,72 @@ impl EloElo {
             default_elo,
         );
 
-        let (left, right) = spawelo::shuffle_teams(
+        let history = self
+            .history
+            .entries
+            .get(&self.selected_game)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        match spawelo::shuffle_teams(
             left.into_iter().chain(right),
             &self.lose_streaks_for_current_lobby(),
+            history,
             self.shuffle_temperature,
+            self.seed,
             &self.options.spawelo,
-        );
+            self.rating_model.as_ref(),
+        ) {
+            Ok(splits) => {
+                self.shuffle_alternatives = splits;
+                self.apply_shuffle(0);
+            }
+            Err(e) => {
+                warn!("Cannot shuffle teams: {e}");
+                self.shuffle_error = Some(e.to_string());
+            }
+        }
+    }
+
+    fn apply_shuffle(&mut self, index: usize) {
+        if let Some(split) = self.shuffle_alternatives.get(index) {
+            self.left_team = split.left.clone();
+            self.right_team = split.right.clone();
+            self.selected_shuffle = index;
+        }
+    }
+
+    fn select_shuffle(&mut self, selection: ShuffleSelection) {
+        let count = self.current_shuffle_alternatives().len();
+        if count == 0 {
+            warn!("No shuffle alternatives for current teams");
+            return;
+        }
+        let index = match selection {
+            ShuffleSelection::Next => (self.selected_shuffle + 1) % count,
+            ShuffleSelection::Previous => (self.selected_shuffle + count - 1) % count,
+            ShuffleSelection::Index(index) if index < count => index,
+            ShuffleSelection::Index(index) => {
+                warn!("Shuffle alternative {index} out of range");
+                return;
+            }
+        };
+        self.apply_shuffle(index);
+    }
 
-        self.left_team = left;
-        self.right_team = right;
+    /// Alternatives of the last shuffle, empty when players were changed since then.
+    fn current_shuffle_alternatives(&self) -> &[TeamSplit] {
+        let players_of = |left: &BalancedTeam, right: &BalancedTeam| -> HashSet<PlayerId> {
+            left.players.iter().chain(&right.players).cloned().collect()
+        };
+        match self.shuffle_alternatives.first() {
+            Some(split)
+                if players_of(&split.left, &split.right)
+                    == players_of(&self.left_team, &self.right_team) =>
+            {
+                &self.shuffle_alternatives
+            }
+            _ => &[],
+        }
     }
 
     fn lose_streaks_for_current_lobby(&self) -> HashMap<PlayerId, i32> {
@@ -528,6 +888,7 @@ impl EloElo {
     fn recalculate_elo_from_history(&mut self) {
         info!("Reloading history");
         self.history = unwrap_or_def_verbose(store::load_history());
+        self.history_revision += 1;
 
         info!("Recalculating {} elo from history", &self.selected_game);
 
@@ -671,7 +1032,7 @@ impl EloElo {
         expected == self.lobby
     }
 
-    fn send_match_result(&self, info: MatchInfo) {
+    fn send_match_result(&self, info: &MatchInfo) {
         if info.fake {
             return;
         }
@@ -702,7 +1063,7 @@ impl EloElo {
         }
     }
 
-    fn make_history_entry(&self, info: MatchInfo) -> HistoryEntry {
+    fn make_history_entry(&self, info: &MatchInfo) -> HistoryEntry {
         let (winner, loser) = match info.winner {
             Team::Left => (
                 self.left_team.players.clone(),
@@ -713,13 +1074,25 @@ impl EloElo {
                 self.left_team.players.clone(),
             ),
         };
+        let timestamp = Local::now();
         HistoryEntry {
-            timestamp: Local::now(),
+            id: MatchId::from_timestamp(&timestamp),
+            timestamp,
             winner,
             loser,
             scale: info.scale,
             duration: info.duration,
             fake: info.fake,
+            seed: Some(self.seed),
+            winner_side: Some(info.winner),
+            heroes: self
+                .match_heroes
+                .played
+                .clone()
+                .into_iter()
+                .chain(info.heroes.clone())
+                .collect(),
+            offered_heroes: self.match_heroes.offered.clone(),
         }
     }
 
@@ -734,26 +1107,29 @@ impl EloElo {
         }
     }
 
-    fn build_ui_history(&self) -> ui_state::UiHistory {
-        let entries = self
-            .history
+    fn query_history(&self, request_id: RequestId, query: HistoryQuery, outdated_fakes: bool) {
+        let query = HistoryQuery {
+            fakes_since: (!outdated_fakes)
+                .then(|| spawelo::fake_match_deadline(&self.options.spawelo.ml_elo, Local::now())),
+            ..query
+        };
+        let page = self.history.query(&query);
+        let entries = page
             .entries
-            .keys()
-            .map(|game| {
-                let entries = self.history_for_elo_calc(game);
-                (
-                    game.clone(),
-                    entries
-                        .into_iter()
-                        .map(|entry| UiHistoryEntry {
-                            metadata: self.build_ui_history_entry_metadata(&entry),
-                            entry,
-                        })
-                        .collect(),
-                )
+            .into_iter()
+            .map(|entry| UiHistoryEntry {
+                metadata: self.build_ui_history_entry_metadata(entry),
+                entry: entry.clone(),
             })
             .collect();
-        UiHistory { entries }
+        self.message_bus.send(Message::Reply(
+            request_id,
+            Reply::HistoryPage(UiHistoryPage {
+                game: query.game,
+                entries,
+                next_cursor: page.next_cursor,
+            }),
+        ));
     }
 
     fn build_ui_history_entry_metadata(&self, entry: &HistoryEntry) -> MatchMetadata {
@@ -773,16 +1149,45 @@ impl EloElo {
                 self.default_elo_for_current_game(),
             )
             .collect();
-        let winner_elo = spawelo::calculate_team_real_elo(&winner);
-        let loser_elo = spawelo::calculate_team_real_elo(&loser);
         MatchMetadata {
-            winner_elo,
-            loser_elo,
-            winner_chance: spawelo::calculate_win_prediction(winner_elo, loser_elo),
+            winner_elo: self.rating_model.team_elo(&winner),
+            loser_elo: self.rating_model.team_elo(&loser),
+            winner_chance: self
+                .rating_model
+                .win_probability(&winner, &loser, entry.winner_side),
         }
     }
 }
 
+fn find_match(history: &History, id: &MatchId) -> Result<(GameId, usize), CorrectionError> {
+    history
+        .entries
+        .iter()
+        .find_map(|(game, entries)| {
+            entries
+                .iter()
+                .position(|e| e.id == *id)
+                .map(|index| (game.clone(), index))
+        })
+        .ok_or_else(|| CorrectionError::NotFound(id.clone()))
+}
+
+fn mk_correction_commit_message(game: &GameId, record: &HistoryCorrection) -> String {
+    let action = if record.current.is_some() {
+        "Amend"
+    } else {
+        "Void"
+    };
+    [
+        format!("{action} {game} match {}", record.match_id),
+        String::from(""),
+        record.reason.clone(),
+        String::from(""),
+        format!("Corrected-by: {}", record.author),
+    ]
+    .join("\n")
+}
+
 fn remove_player_id(players: &mut Vec<PlayerId>, player_id: &PlayerId) -> Option<PlayerId> {
     players
         .iter()
@@ -804,6 +1209,25 @@ fn with_alternative_matches(p: &str) -> Vec<String> {
 mod test {
     use super::*;
 
+    #[test]
+    fn find_match_reports_unknown_id() {
+        let entry = HistoryEntry {
+            id: MatchId::from("known"),
+            ..HistoryEntry::test_entry(0, ["a"], ["b"])
+        };
+        let history = History {
+            entries: HashMap::from([(GameId::from("game"), vec![entry])]),
+        };
+        assert_eq!(
+            find_match(&history, &MatchId::from("known")),
+            Ok((GameId::from("game"), 0))
+        );
+        assert_eq!(
+            find_match(&history, &MatchId::from("unknown")),
+            Err(CorrectionError::NotFound(MatchId::from("unknown")))
+        );
+    }
+
     #[test]
     fn test_with_alternative_matches() {
         assert_eq!(
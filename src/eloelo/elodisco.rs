@@ -1,10 +1,12 @@
 use crate::eloelo::config::Config;
 use crate::eloelo::elodisco::async_elodisco::EloDisco;
 use crate::eloelo::elodisco::bot_state::BotState;
-use crate::eloelo::elodisco::dota_bot::Hero;
-use crate::eloelo::message_bus::MessageBus;
+use crate::eloelo::message_bus::{
+    Message, MessageBus, PlayerStatsReport, Reply, StatsPlayer, UiCommand,
+};
 use crate::utils::print_err;
 use anyhow::{Context as _, Error, Result};
+use eloelo_model::hero::Hero;
 use eloelo_model::player::DiscordUsername;
 use futures_util::lock::Mutex;
 use futures_util::StreamExt as _;
@@ -12,6 +14,7 @@ use itertools::Itertools;
 use log::{info, warn};
 use poise::serenity_prelude as serenity;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub(crate) mod async_elodisco;
 pub(crate) mod bot_state;
@@ -24,6 +27,8 @@ mod utils;
 type SharedEloDisco = Arc<Mutex<EloDisco>>;
 type Context<'a> = poise::Context<'a, SharedEloDisco, Error>;
 
+const STATS_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Rerolls assigned heroes
 #[poise::command(slash_command)]
 async fn reroll(ctx: Context<'_>) -> Result<()> {
@@ -34,6 +39,59 @@ async fn reroll(ctx: Context<'_>) -> Result<()> {
     Ok(())
 }
 
+/// Displays your wins, loses, best teammates and nemeses
+#[poise::command(slash_command)]
+async fn stats(ctx: Context<'_>) -> Result<()> {
+    let username = DiscordUsername::from(ctx.author().name.as_str());
+    // Elodisco must be unlocked while waiting, the reply is delivered through its message handler.
+    let message_bus = ctx.data().lock().await.message_bus().clone();
+    let report = request_player_stats(&message_bus, username).await?;
+    ctx.send(messages::ephemeral_player_stats_reply(&report))
+        .await?;
+    Ok(())
+}
+
+async fn request_player_stats(
+    message_bus: &MessageBus,
+    username: DiscordUsername,
+) -> Result<PlayerStatsReport> {
+    let report = message_bus
+        .request(
+            |request_id| UiCommand::PlayerStats {
+                request_id,
+                player: StatsPlayer::Discord(username),
+            },
+            |reply| match reply {
+                Reply::PlayerStats(report) => Some(report),
+                _ => None,
+            },
+            STATS_TIMEOUT,
+        )
+        .await
+        .context("Player stats request failed")?;
+    Ok(report)
+}
+
+/// Records the hero you play in the current match
+#[poise::command(slash_command)]
+async fn played(
+    ctx: Context<'_>,
+    #[description = "Hero you play"]
+    #[autocomplete = "played_autocomplete"]
+    hero: String,
+) -> Result<()> {
+    let hero = Hero::try_from(hero)?;
+    let username = DiscordUsername::from(ctx.author().name.as_str());
+    let message_bus = ctx.data().lock().await.message_bus().clone();
+    message_bus.send(Message::UiCommand(UiCommand::SetPlayedHero {
+        player: username,
+        hero: hero.clone(),
+    }));
+    ctx.send(messages::ephemeral_reply(format!("You play {hero}")))
+        .await?;
+    Ok(())
+}
+
 /// Displays configuration status
 #[poise::command(slash_command)]
 async fn debug(ctx: Context<'_>) -> Result<()> {
@@ -339,6 +397,13 @@ async fn unallow_autocomplete<'a>(
     heroes_to_autocomplete(state.allowed_heroes.iter(), partial).into_iter()
 }
 
+async fn played_autocomplete(
+    _ctx: Context<'_>,
+    partial: &str,
+) -> impl Iterator<Item = serenity::AutocompleteChoice> {
+    heroes_to_autocomplete(Hero::all().iter(), partial).into_iter()
+}
+
 /// Show this menu
 #[poise::command(prefix_command, track_edits, slash_command)]
 pub async fn help(
@@ -378,7 +443,7 @@ pub async fn run(config: Config, bot_state: BotState, message_bus: MessageBus) {
 
         let framework = poise::Framework::builder()
             .options(poise::FrameworkOptions {
-                commands: vec![reroll(), debug(), show(), pool(), help()],
+                commands: vec![reroll(), debug(), show(), pool(), stats(), played(), help()],
                 ..Default::default()
             })
             .setup(|ctx, _ready, framework| {
@@ -133,6 +133,10 @@ impl EloDisco {
             .unwrap_or_default()
     }
 
+    pub fn message_bus(&self) -> &MessageBus {
+        &self.message_bus
+    }
+
     pub fn dota_bot_mut(&mut self) -> &mut DotaBot {
         &mut self.dota_bot
     }
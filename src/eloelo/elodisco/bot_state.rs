@@ -4,7 +4,7 @@ use chrono::{DateTime, Local};
 use eloelo_model::player::DiscordUsername;
 use serde::{Deserialize, Serialize};
 
-use super::dota_bot::Hero;
+use eloelo_model::hero::Hero;
 
 //TODO: either introduce separate DiscordUsername type or make PlayerId == username + add display name
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
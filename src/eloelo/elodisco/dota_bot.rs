@@ -3,78 +3,16 @@ use crate::eloelo::config::Config;
 use crate::eloelo::elodisco::hero_assignment_strategy::{
     DotaTeam, HeroAssignmentStrategy, PlayerInfo, RandomHeroPool, TaggedHeroPool,
 };
-use crate::eloelo::message_bus::{Event, HeroesAssigned, MatchStart, Message, MessageBus};
-use anyhow::{format_err, Error, Result};
+use crate::eloelo::message_bus::{
+    Event, HeroesAssigned, MatchStart, Message, MessageBus, UiCommand,
+};
+use anyhow::Result;
 use chrono::{DateTime, Local};
+use eloelo_model::hero::Hero;
 use eloelo_model::player::DiscordUsername;
 use eloelo_model::PlayerId;
 use log::{debug, info};
-use serde::{Deserialize, Serialize};
-use std::borrow::Borrow;
 use std::collections::{HashMap, HashSet};
-use std::fmt::Display;
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub struct Hero(String);
-
-impl Hero {
-    pub fn all() -> HashSet<Hero> {
-        include_str!("dota_heroes.csv")
-            .split("\n")
-            .map(|s| Hero(String::from(s.split(',').next().unwrap().trim())))
-            .collect()
-    }
-
-    pub fn all_alphabetical() -> Vec<Hero> {
-        let mut heroes: Vec<_> = Hero::all().into_iter().collect();
-        heroes.sort();
-        heroes
-    }
-
-    pub fn as_str(&self) -> &str {
-        &self.0
-    }
-}
-
-impl TryFrom<String> for Hero {
-    type Error = Error;
-
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        if Hero::all().contains(value.as_str()) {
-            Ok(Hero(value))
-        } else {
-            Err(format_err!(
-                "Incorrect hero name: \"{}\". See `all` for list of valid names.",
-                value
-            ))
-        }
-    }
-}
-
-#[cfg(test)]
-impl From<&str> for Hero {
-    fn from(value: &str) -> Self {
-        Hero::try_from(String::from(value)).unwrap()
-    }
-}
-
-impl Display for Hero {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
-    }
-}
-
-impl Borrow<str> for Hero {
-    fn borrow(&self) -> &str {
-        &self.0
-    }
-}
-
-impl AsRef<Hero> for Hero {
-    fn as_ref(&self) -> &Hero {
-        &self
-    }
-}
 
 pub enum RerollResult {
     NewPool(Vec<Hero>),
@@ -116,6 +54,10 @@ impl DotaBot {
         match message {
             Message::Event(Event::MatchStart(match_start)) => {
                 let assignments = self.on_match_start(match_start).await;
+                self.message_bus
+                    .send(Message::UiCommand(UiCommand::OfferedHeroes(
+                        assignments.clone(),
+                    )));
                 self.message_bus
                     .send(Message::Event(Event::HeroesAssigned(HeroesAssigned {
                         match_start: match_start.clone(),
@@ -200,7 +142,9 @@ impl DotaBot {
     ) -> HashMap<DiscordUsername, Vec<Hero>> {
         let hero_pools = self.make_hero_pools(&match_start);
         self.hero_assign_strategy.clear();
-        let hero_assignments = self.hero_assign_strategy.assign_heroes(hero_pools);
+        let hero_assignments = self
+            .hero_assign_strategy
+            .assign_heroes(hero_pools, match_start.seed);
 
         hero_assignments
             .into_iter()
@@ -223,9 +167,14 @@ impl DotaBot {
             username,
             hero_pool.join(", ")
         );
-        Ok(RerollResult::NewPool(
-            self.hero_assign_strategy.reroll(username, hero_pool)?,
-        ))
+        let new_pool = self.hero_assign_strategy.reroll(username, hero_pool)?;
+        if !new_pool.is_empty() {
+            self.message_bus
+                .send(Message::UiCommand(UiCommand::OfferedHeroes(HashMap::from(
+                    [(username.clone(), new_pool.clone())],
+                ))));
+        }
+        Ok(RerollResult::NewPool(new_pool))
     }
 
     pub async fn get_state(&self) -> HashMap<DiscordUsername, DotaBotState> {
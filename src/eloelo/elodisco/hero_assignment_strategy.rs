@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 
 use anyhow::Error;
+use eloelo_model::hero::Hero;
 use eloelo_model::player::DiscordUsername;
-
-use crate::eloelo::elodisco::dota_bot::Hero;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
 
 mod random_hero_pool;
 mod tagged_hero_pool;
@@ -27,9 +28,11 @@ pub struct PlayerInfo {
 }
 
 pub trait HeroAssignmentStrategy {
+    /// Same `seed` and hero pools always give the same assignment. Rerolls continue from there.
     fn assign_heroes(
         &mut self,
         hero_pools: Vec<(PlayerInfo, Vec<Hero>)>,
+        seed: u64,
     ) -> HashMap<PlayerInfo, Vec<Hero>>;
 
     fn reroll(
@@ -40,3 +43,38 @@ pub trait HeroAssignmentStrategy {
 
     fn clear(&mut self);
 }
+
+/// Random number generator of a strategy, reseeded by every
+/// [`HeroAssignmentStrategy::assign_heroes`].
+#[derive(Clone)]
+pub struct StrategyRng(StdRng);
+
+impl StrategyRng {
+    pub fn reseed(&mut self, seed: u64) {
+        self.0 = StdRng::seed_from_u64(seed);
+    }
+}
+
+impl Default for StrategyRng {
+    fn default() -> Self {
+        Self(StdRng::seed_from_u64(0))
+    }
+}
+
+impl RngCore for StrategyRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
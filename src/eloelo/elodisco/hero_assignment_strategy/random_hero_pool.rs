@@ -1,28 +1,31 @@
 use std::collections::{HashMap, HashSet};
 
 use anyhow::{format_err, Error};
+use eloelo_model::hero::Hero;
 use eloelo_model::player::DiscordUsername;
 use itertools::Itertools;
 use rand::seq::SliceRandom;
 
-use crate::eloelo::elodisco::{
-    dota_bot::Hero,
-    hero_assignment_strategy::{HeroAssignmentStrategy, PlayerInfo},
+use crate::eloelo::elodisco::hero_assignment_strategy::{
+    HeroAssignmentStrategy, PlayerInfo, StrategyRng,
 };
 
 #[derive(Default)]
 pub struct RandomHeroPool {
     pub hero_assignement: HashMap<PlayerInfo, Vec<Hero>>,
     pub taken: HashSet<Hero>,
+    rng: StrategyRng,
 }
 
-impl HeroAssignmentStrategy for RandomHeroPool {
-    fn assign_heroes(
+impl RandomHeroPool {
+    fn assign(
         &mut self,
         mut hero_pools: Vec<(PlayerInfo, Vec<Hero>)>,
     ) -> HashMap<PlayerInfo, Vec<Hero>> {
-        // Sort players by pool length
-        hero_pools.sort_by_key(|v| (v.1.len(), rand::random::<i32>()));
+        // Sort players by pool length, in random order otherwise
+        hero_pools.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        hero_pools.shuffle(&mut self.rng);
+        hero_pools.sort_by_key(|v| v.1.len());
         let max_num_of_heroes = hero_pools
             .iter()
             .map(|v| v.0.number_of_heroes_shown)
@@ -40,7 +43,8 @@ impl HeroAssignmentStrategy for RandomHeroPool {
                 {
                     continue;
                 }
-                hero_pool.shuffle(&mut rand::thread_rng());
+                hero_pool.sort();
+                hero_pool.shuffle(&mut self.rng);
                 while let Some(hero) = hero_pool.pop() {
                     if self.taken.contains(&hero) {
                         continue;
@@ -56,6 +60,17 @@ impl HeroAssignmentStrategy for RandomHeroPool {
         }
         self.hero_assignement.clone()
     }
+}
+
+impl HeroAssignmentStrategy for RandomHeroPool {
+    fn assign_heroes(
+        &mut self,
+        hero_pools: Vec<(PlayerInfo, Vec<Hero>)>,
+        seed: u64,
+    ) -> HashMap<PlayerInfo, Vec<Hero>> {
+        self.rng.reseed(seed);
+        self.assign(hero_pools)
+    }
 
     fn reroll(
         &mut self,
@@ -79,7 +94,7 @@ impl HeroAssignmentStrategy for RandomHeroPool {
         let old_picks = self.hero_assignement.get(&player).unwrap().clone();
         self.hero_assignement.get_mut(&player).unwrap().clear();
         let mut assignement =
-            self.assign_heroes(vec![(player.clone(), hero_pool.into_iter().collect_vec())]);
+            self.assign(vec![(player.clone(), hero_pool.into_iter().collect_vec())]);
         let new_picks = assignement.entry(player.clone()).or_default().to_vec();
         self.taken = self
             .taken
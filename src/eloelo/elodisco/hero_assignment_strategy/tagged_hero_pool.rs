@@ -6,19 +6,14 @@ use std::{
 };
 
 use anyhow::{bail, format_err, Context, Error, Result};
+use eloelo_model::hero::{Hero, HEROES_CSV};
 use eloelo_model::player::DiscordUsername;
 use itertools::Itertools;
 use log::{info, warn};
-use rand::{
-    seq::{IteratorRandom, SliceRandom},
-    thread_rng,
-};
+use rand::seq::{IteratorRandom, SliceRandom};
 
-use crate::eloelo::elodisco::hero_assignment_strategy::DotaTeam;
-use crate::eloelo::elodisco::{
-    dota_bot::Hero,
-    hero_assignment_strategy::{HeroAssignmentStrategy, PlayerInfo},
-};
+use crate::eloelo::elodisco::hero_assignment_strategy::{DotaTeam, StrategyRng};
+use crate::eloelo::elodisco::hero_assignment_strategy::{HeroAssignmentStrategy, PlayerInfo};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum HeroTag {
@@ -52,6 +47,7 @@ pub struct TaggedHeroPool {
     pub hero_assignement: HashMap<PlayerInfo, Vec<Hero>>,
     pub taken: HashSet<Hero>,
     pub hero_similarity: HashMap<Hero, Vec<Hero>>,
+    rng: StrategyRng,
 }
 
 impl TaggedHeroPool {
@@ -64,7 +60,7 @@ impl TaggedHeroPool {
     }
 
     fn read_tags() -> HashMap<HeroTag, HashSet<Hero>> {
-        include_str!("../dota_heroes.csv")
+        HEROES_CSV
             .split("\n")
             .map(|s| {
                 let raw: Vec<&str> = s.split(",").map(|s| s.trim()).collect();
@@ -134,7 +130,8 @@ impl TaggedHeroPool {
     fn assign_random_hero(&mut self, hero_pool: &HashSet<Hero>) -> Option<Hero> {
         hero_pool
             .difference(&self.taken)
-            .choose(&mut rand::thread_rng())
+            .sorted()
+            .choose(&mut self.rng)
             .cloned()
     }
 
@@ -151,7 +148,7 @@ impl TaggedHeroPool {
             info!("Fallback to random hero assignment.");
             return self.assign_random_hero(hero_pool);
         }
-        heroes.into_iter().choose(&mut rand::thread_rng())
+        heroes.into_iter().sorted().choose(&mut self.rng)
     }
 
     fn assign_similar_hero(
@@ -177,21 +174,19 @@ impl TaggedHeroPool {
             }
         }
         if !sampled_similar_heroes.is_empty() {
-            return sampled_similar_heroes
-                .into_iter()
-                .choose(&mut rand::thread_rng());
+            return sampled_similar_heroes.into_iter().choose(&mut self.rng);
         }
         None
     }
 
-    fn players_pairing(t1_len: usize, t2_len: usize) -> Vec<usize> {
+    fn players_pairing(&mut self, t1_len: usize, t2_len: usize) -> Vec<usize> {
         let [t_min, t_max] = if t2_len < t1_len {
             [t2_len, t1_len]
         } else {
             [t1_len, t2_len]
         };
         let mut pairing_order = (0..t_min).collect_vec();
-        pairing_order.shuffle(&mut rand::thread_rng());
+        pairing_order.shuffle(&mut self.rng);
         pairing_order.into_iter().chain(t_min..t_max).collect()
     }
 }
@@ -200,7 +195,9 @@ impl HeroAssignmentStrategy for TaggedHeroPool {
     fn assign_heroes(
         &mut self,
         hero_pools: Vec<(PlayerInfo, Vec<Hero>)>,
+        seed: u64,
     ) -> HashMap<PlayerInfo, Vec<Hero>> {
+        self.rng.reseed(seed);
         let hero_pools_sets = hero_pools
             .into_iter()
             .map(|(name, heroes)| (name, HashSet::from_iter(heroes.iter().cloned())))
@@ -208,19 +205,19 @@ impl HeroAssignmentStrategy for TaggedHeroPool {
         let radiant = hero_pools_sets
             .iter()
             .filter(|(p, _)| p.dota_team == DotaTeam::Radiant)
-            .sorted_by_key(|(p, _)| -p.elo)
+            .sorted_by_key(|(p, _)| (-p.elo, p.name.clone()))
             .collect_vec();
         let dire = hero_pools_sets
             .iter()
             .filter(|(p, _)| p.dota_team == DotaTeam::Dire)
-            .sorted_by_key(|(p, _)| -p.elo)
+            .sorted_by_key(|(p, _)| (-p.elo, p.name.clone()))
             .collect_vec();
         let max_hero_shown = hero_pools_sets
             .iter()
             .map(|(p, _)| p.number_of_heroes_shown)
             .max()
             .unwrap_or(3);
-        let pairing_order = Self::players_pairing(radiant.len(), dire.len());
+        let pairing_order = self.players_pairing(radiant.len(), dire.len());
         let pairs_tag = (0..pairing_order.len())
             .map(HeroTag::next_tag)
             .collect_vec(); // rotate Core -> Support -> Carry -> ... for balanced team composition
@@ -228,7 +225,7 @@ impl HeroAssignmentStrategy for TaggedHeroPool {
             for (pair_id, pair_tag) in zip(pairing_order.iter(), pairs_tag.iter()) {
                 let mut paired_hero: Option<Hero> = None;
                 let mut teams = [&radiant, &dire];
-                teams.shuffle(&mut thread_rng());
+                teams.shuffle(&mut self.rng);
                 for team in teams {
                     if team.len() <= *pair_id {
                         // not even number of players per team
@@ -2,35 +2,33 @@
 mod tests {
     use std::collections::{HashMap, HashSet};
 
+    use eloelo_model::hero::Hero;
     use eloelo_model::player::DiscordUsername;
     use itertools::Itertools;
 
-    use crate::eloelo::elodisco::{
-        dota_bot::Hero,
-        hero_assignment_strategy::{
-            DotaTeam, HeroAssignmentStrategy, PlayerInfo, RandomHeroPool, TaggedHeroPool,
-        },
+    use crate::eloelo::elodisco::hero_assignment_strategy::{
+        DotaTeam, HeroAssignmentStrategy, PlayerInfo, RandomHeroPool, TaggedHeroPool,
     };
 
-    const N: usize = 100;
+    const N: u64 = 100;
 
     fn default_hero_pool() -> Vec<Hero> {
         vec![
-            Hero::from("Puck"),
-            Hero::from("Pudge"),
-            Hero::from("Razor"),
-            Hero::from("Io"),
-            Hero::from("Lion"),
-            Hero::from("Lich"),
+            Hero::new("Puck"),
+            Hero::new("Pudge"),
+            Hero::new("Razor"),
+            Hero::new("Io"),
+            Hero::new("Lion"),
+            Hero::new("Lich"),
         ]
     }
 
     fn small_hero_pool() -> Vec<Hero> {
         vec![
-            Hero::from("Puck"),
-            Hero::from("Pudge"),
-            Hero::from("Io"),
-            Hero::from("Lion"),
+            Hero::new("Puck"),
+            Hero::new("Pudge"),
+            Hero::new("Io"),
+            Hero::new("Lion"),
         ]
     }
 
@@ -108,10 +106,10 @@ mod tests {
     // every HeroPoolGenerator should pass this test
     #[test]
     fn test_base_random() {
-        for _ in 0..N {
+        for seed in 0..N {
             let players = default_players(default_hero_pool());
             let mut random_assign = RandomHeroPool::default();
-            let players_assignement = random_assign.assign_heroes(players);
+            let players_assignement = random_assign.assign_heroes(players, seed);
             assert!(no_duplicates(&players_assignement));
             for (player, assignement) in players_assignement {
                 assert_eq!(player.number_of_heroes_shown as usize, assignement.len());
@@ -126,10 +124,10 @@ mod tests {
     // every HeroPoolGenerator should pass this test
     #[test]
     fn test_small_pool_random() {
-        for _ in 0..N {
+        for seed in 0..N {
             let players = default_players(small_hero_pool());
             let mut random_assign = RandomHeroPool::default();
-            let players_assignement = random_assign.assign_heroes(players);
+            let players_assignement = random_assign.assign_heroes(players, seed);
             assert!(no_duplicates(&players_assignement));
             for (_, assignement) in players_assignement {
                 assert_eq!(assignement.len(), 1);
@@ -142,10 +140,10 @@ mod tests {
     fn test_randomness_random() {
         let mut all_assignements = Vec::new();
         let mut random_assign = RandomHeroPool::default();
-        for _ in 0..N {
+        for seed in 0..N {
             let players = few_players(default_hero_pool());
             random_assign.clear();
-            let players_assignement = random_assign.assign_heroes(players);
+            let players_assignement = random_assign.assign_heroes(players, seed);
             all_assignements.push(players_assignement);
         }
         assert!(
@@ -161,10 +159,10 @@ mod tests {
     #[test]
     fn test_base_tags() {
         let mut tag_assign = TaggedHeroPool::new();
-        for _ in 0..N {
+        for seed in 0..N {
             let players: Vec<(PlayerInfo, Vec<Hero>)> = default_players(default_hero_pool());
             tag_assign.clear();
-            let players_assignement = tag_assign.assign_heroes(players);
+            let players_assignement = tag_assign.assign_heroes(players, seed);
             assert!(no_duplicates(&players_assignement));
             for (player, assignement) in players_assignement.iter() {
                 assert_eq!(player.number_of_heroes_shown as usize, assignement.len());
@@ -179,10 +177,10 @@ mod tests {
     #[test]
     fn test_small_pool_tags() {
         let mut tag_assign = TaggedHeroPool::new();
-        for _ in 0..N {
+        for seed in 0..N {
             let players = default_players(small_hero_pool());
             tag_assign.clear();
-            let players_assignement = tag_assign.assign_heroes(players);
+            let players_assignement = tag_assign.assign_heroes(players, seed);
             assert!(no_duplicates(&players_assignement));
             for (_, assignement) in players_assignement {
                 assert_eq!(assignement.len(), 1);
@@ -194,10 +192,10 @@ mod tests {
     fn test_randomness_tags() {
         let mut all_assignements = Vec::new();
         let mut tag_assign = TaggedHeroPool::new();
-        for _ in 0..N {
+        for seed in 0..N {
             let players = few_players(default_hero_pool());
             tag_assign.clear();
-            let players_assignement = tag_assign.assign_heroes(players);
+            let players_assignement = tag_assign.assign_heroes(players, seed);
             all_assignements.push(players_assignement);
         }
         assert!(
@@ -210,13 +208,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_same_seed_same_assignment() {
+        let strategies: [Box<dyn Fn() -> Box<dyn HeroAssignmentStrategy>>; 2] = [
+            Box::new(|| Box::new(RandomHeroPool::default())),
+            Box::new(|| Box::new(TaggedHeroPool::new())),
+        ];
+        for make_strategy in strategies {
+            let assign = |seed| {
+                let mut players = default_players(default_hero_pool());
+                players.reverse();
+                make_strategy().assign_heroes(players, seed)
+            };
+            assert_eq!(assign(7), assign(7));
+            assert_eq!(
+                assign(7),
+                make_strategy().assign_heroes(default_players(default_hero_pool()), 7)
+            );
+        }
+    }
+
     #[test]
     fn test_tags_consistent() {
         let mut tag_assign = TaggedHeroPool::new();
-        for _ in 0..N {
+        for seed in 0..N {
             let players = default_players(default_hero_pool());
             tag_assign.clear();
-            let players_assignement = tag_assign.assign_heroes(players);
+            let players_assignement = tag_assign.assign_heroes(players, seed);
             for (_, assignement) in players_assignement.iter() {
                 assert_eq!(
                     assignement
@@ -233,10 +251,10 @@ mod tests {
     #[test]
     fn test_tags_correspondence() {
         let mut tag_assign = TaggedHeroPool::new();
-        for _ in 0..N {
+        for seed in 0..N {
             let players = default_players(default_hero_pool());
             tag_assign.clear();
-            let players_assignement = tag_assign.assign_heroes(players);
+            let players_assignement = tag_assign.assign_heroes(players, seed);
             for mut pair in players_assignement
                 .into_iter()
                 .sorted_by_key(|(p, _)| p.elo)
@@ -270,10 +288,10 @@ mod tests {
     // every HeroPoolGenerator should pass this test
     #[test]
     fn test_reroll_random() {
-        for _ in 0..N {
+        for seed in 0..N {
             let players = few_players(small_hero_pool());
             let mut random_assign = RandomHeroPool::default();
-            let players_assignement = random_assign.assign_heroes(players.clone());
+            let players_assignement = random_assign.assign_heroes(players.clone(), seed);
             let reroll = random_assign
                 .reroll(&players[0].0.name, players[0].1.clone())
                 .unwrap();
@@ -287,10 +305,10 @@ mod tests {
     #[test]
     fn test_reroll_tags() {
         let mut tag_assign = TaggedHeroPool::new();
-        for _ in 0..N {
+        for seed in 0..N {
             let players = few_players(default_hero_pool());
             tag_assign.clear();
-            let players_assignement = tag_assign.assign_heroes(players.clone());
+            let players_assignement = tag_assign.assign_heroes(players.clone(), seed);
             let reroll = tag_assign
                 .reroll(&players[0].0.name, players[0].1.clone())
                 .unwrap();
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 
 use crate::eloelo::elodisco::bot_state::DotaBotState;
-use crate::eloelo::elodisco::dota_bot::{Hero, RerollResult};
-use crate::eloelo::message_bus::MatchStart;
+use crate::eloelo::elodisco::dota_bot::RerollResult;
+use crate::eloelo::message_bus::{MatchStart, PlayerStatsReport};
 use crate::utils;
+use eloelo_model::hero::Hero;
 use eloelo_model::player::DiscordUsername;
+use eloelo_model::stats::{Relationship, WinLoss};
 use eloelo_model::PlayerId;
 use log::info;
 use poise::serenity_prelude as serenity;
@@ -116,6 +118,64 @@ pub fn ephemeral_dota_bot_hero_list_reply<'a>(
         .ephemeral(true)
 }
 
+// Teammates and opponents met fewer times are left out of the summary.
+const MIN_RELATIONSHIP_MATCHES: u32 = 3;
+const RELATIONSHIPS_SHOWN: usize = 3;
+
+pub fn ephemeral_player_stats_reply(report: &PlayerStatsReport) -> poise::CreateReply {
+    let Some(stats) = report.players.first() else {
+        return ephemeral_reply(format!(
+            "You have not played any **{}** match yet.",
+            report.game
+        ));
+    };
+    let frequent = |relationships: &[Relationship]| -> Vec<Relationship> {
+        relationships
+            .iter()
+            .filter(|r| r.record.matches() >= MIN_RELATIONSHIP_MATCHES)
+            .cloned()
+            .collect()
+    };
+    let teammates = frequent(&stats.teammates);
+    let opponents = frequent(&stats.opponents);
+    let content = format!(
+        "**{}** stats of **{}**\n\
+        Overall: {}\n\
+        Even: {}, Advantage: {}, Pwnage: {}\n\
+        Best teammates: {}\n\
+        Nemeses: {}",
+        report.game,
+        stats.player,
+        win_loss_str(&stats.overall),
+        win_loss_str(&stats.by_scale.even),
+        win_loss_str(&stats.by_scale.advantage),
+        win_loss_str(&stats.by_scale.pwnage),
+        relationships_str(teammates.iter().take(RELATIONSHIPS_SHOWN)),
+        relationships_str(opponents.iter().rev().take(RELATIONSHIPS_SHOWN)),
+    );
+    ephemeral_reply(content)
+}
+
+fn win_loss_str(record: &WinLoss) -> String {
+    format!(
+        "{}W {}L ({:.0}%)",
+        record.wins,
+        record.loses,
+        record.win_rate() * 100.0
+    )
+}
+
+fn relationships_str<'a>(relationships: impl Iterator<Item = &'a Relationship>) -> String {
+    let relationships: Vec<_> = relationships
+        .map(|r| format!("{} {}", r.player, win_loss_str(&r.record)))
+        .collect();
+    if relationships.is_empty() {
+        String::from("Not enough matches.")
+    } else {
+        relationships.join(", ")
+    }
+}
+
 pub fn ephemeral_reply(content: impl Into<String>) -> poise::CreateReply {
     poise::CreateReply::default()
         .content(content)
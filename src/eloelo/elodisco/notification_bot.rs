@@ -1,12 +1,13 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
+use eloelo_model::hero::Hero;
 use eloelo_model::player::{DiscordUsername, PlayerDb};
 use itertools::join;
 use log::{error, info};
 
 use crate::eloelo::config::Config;
-use crate::eloelo::elodisco::dota_bot::{Hero, RerollResult};
+use crate::eloelo::elodisco::dota_bot::RerollResult;
 use crate::eloelo::elodisco::messages;
 use crate::eloelo::elodisco::utils::DirectMessenger;
 use crate::eloelo::message_bus::{
@@ -216,6 +217,7 @@ async fn send_message(
 struct PlayerEmbedData {
     name: String,
     rank: i32,
+    uncertainty: Option<i32>,
     recommendations: String,
 }
 
@@ -244,6 +246,7 @@ impl TeamEmbedData {
                     .unwrap_or("INVALID")
                     .to_string(),
                 rank: *elo,
+                uncertainty: team.uncertainty.get(player_id).copied(),
                 recommendations: hero_assignments
                     .get(&discord_username)
                     .map(|ha| join(ha, ", "))
@@ -266,7 +269,10 @@ fn make_team_embed(team: TeamEmbedData, colour: serenity::Colour) -> serenity::C
         .title(team.name)
         .fields(players.into_iter().map(|p| {
             (
-                format!("{}   [{}]", p.name, p.rank),
+                match p.uncertainty {
+                    Some(u) => format!("{}   [{} ±{}]", p.name, p.rank, u),
+                    None => format!("{}   [{}]", p.name, p.rank),
+                },
                 format!("{}", p.recommendations),
                 false,
             )
@@ -1,23 +1,69 @@
 use anyhow::Result;
 use bytes::Bytes;
+use chrono::{DateTime, Local};
+use eloelo_model::hero::Hero;
+use eloelo_model::history::{HistoryAmendment, MatchId};
+use eloelo_model::history_query::HistoryQuery;
 use eloelo_model::player::{DiscordUsername, Player, PlayerDb};
+use eloelo_model::stats::PlayerStats;
 use eloelo_model::{GameId, PlayerId, Team, WinScale};
 use futures_util::{Stream, StreamExt};
 use log::error;
 use serde::Serialize;
+use spawelo::{BacktestReport, PairSynergy, PlayerRatingHistory, SpaweloOptions, TuningResult};
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tokio::sync::broadcast::{Receiver, Sender};
 use tokio_stream::wrappers::BroadcastStream;
 
-use crate::eloelo::elodisco::dota_bot::Hero;
 use crate::eloelo::options::EloEloOptions;
 
-use super::ui_state::UiState;
+use super::ui_state::{UiHistoryPage, UiState};
+
+/// Identifies a request, see [`MessageBus::request`].
+pub type RequestId = u64;
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Clone)]
 pub(crate) struct MessageBus(Sender<Message>);
 
+#[derive(Debug)]
+pub(crate) enum RequestError {
+    Closed,
+    TimedOut,
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RequestError::Closed => "Message bus closed",
+            RequestError::TimedOut => "Request timed out",
+        })
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum CorrectionError {
+    NotFound(MatchId),
+    StoreFailed(String),
+}
+
+impl Display for CorrectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CorrectionError::NotFound(id) => write!(f, "Match {id} not found"),
+            CorrectionError::StoreFailed(e) => write!(f, "Failed to store history correction: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for CorrectionError {}
+
 impl MessageBus {
     pub fn new() -> Self {
         let (sender, _) = tokio::sync::broadcast::channel(100);
@@ -33,6 +79,31 @@ impl MessageBus {
     pub fn subscribe(&self) -> MessageBusSubscription {
         MessageBusSubscription(self.0.subscribe())
     }
+
+    /// Sends the command made for a fresh request id and waits for the reply to it, which
+    /// `expected` picks the content of.
+    pub async fn request<T>(
+        &self,
+        command: impl FnOnce(RequestId) -> UiCommand,
+        expected: impl Fn(Reply) -> Option<T>,
+        timeout: Duration,
+    ) -> Result<T, RequestError> {
+        let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        // Subscribed before sending, so the reply cannot be missed
+        let mut replies = Box::pin(self.subscribe().stream().filter_map(|m| {
+            let reply = match m {
+                Ok(Message::Reply(id, reply)) if id == request_id => expected(reply),
+                _ => None,
+            };
+            async move { reply }
+        }));
+        self.send(Message::UiCommand(command(request_id)));
+        match tokio::time::timeout(timeout, replies.next()).await {
+            Ok(Some(reply)) => Ok(reply),
+            Ok(None) => Err(RequestError::Closed),
+            Err(_) => Err(RequestError::TimedOut),
+        }
+    }
 }
 
 pub(crate) struct MessageBusSubscription(Receiver<Message>);
@@ -75,9 +146,12 @@ impl MessageBusSubscription {
 
 #[derive(Clone, Debug)]
 pub(crate) enum Message {
+    /// Sent to all UI clients
     UiUpdate(UiUpdate),
     UiCommand(UiCommand),
     Event(Event),
+    /// Answer to the request with the id, only its sender waits for it
+    Reply(RequestId, Reply),
 }
 
 impl From<UiState> for Message {
@@ -91,6 +165,32 @@ impl From<UiState> for Message {
 pub enum UiUpdate {
     State(UiState),
     DiscordInfo(Vec<DiscordPlayerInfo>),
+    Tuning(TuningResult),
+}
+
+#[derive(Debug, Clone)]
+pub enum Reply {
+    HistoryPage(UiHistoryPage),
+    RatingHistory(Vec<PlayerRatingHistory>),
+    Backtest(BacktestReport),
+    PairSynergies(PairSynergies),
+    PlayerStats(PlayerStatsReport),
+    MatchCorrected(Result<(), CorrectionError>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairSynergies {
+    pub game: GameId,
+    pub strongest: Vec<PairSynergy>,
+    pub weakest: Vec<PairSynergy>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerStatsReport {
+    pub game: GameId,
+    pub players: Vec<PlayerStats>,
 }
 
 #[derive(Debug, Clone)]
@@ -100,12 +200,16 @@ pub struct MatchStart {
     pub player_db: PlayerDb,
     pub left_team: MatchStartTeam,
     pub right_team: MatchStartTeam,
+    /// Seed for hero assignment, recorded in history with the match
+    pub seed: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct MatchStartTeam {
     // TODO(j): since we have full playerdb in MatchStart, maybe we shouldn't send elo here?
     pub players: HashMap<PlayerId, i32>,
+    /// Rating deviation of players, when the rating model tracks it
+    pub uncertainty: HashMap<PlayerId, i32>,
     pub name: String,
 }
 
@@ -174,6 +278,13 @@ pub enum Event {
     DotaScreenshotReceived(Bytes, Option<ImageFormat>),
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum ShuffleSelection {
+    Next,
+    Previous,
+    Index(usize),
+}
+
 #[derive(Clone, Debug)]
 pub enum UiCommand {
     InitializeUi,
@@ -190,25 +301,104 @@ pub enum UiCommand {
     CallToLobby,
     CallPlayer(PlayerId),
     StartMatch,
-    ShuffleTeams,
+    /// Shuffles teams with the given seed, or a fresh one
+    ShuffleTeams(Option<u64>),
     RefreshElo,
     FinishMatch(FinishMatch),
     AddLobbyScreenshotData(Vec<String>),
     UpdateOptions(EloEloOptions),
     SetShuffleTemperature(i32),
+    /// Applies one of the alternative splits proposed by the last shuffle
+    SelectShuffle(ShuffleSelection),
+    Backtest {
+        request_id: RequestId,
+        backtest: Backtest,
+    },
+    TuneOptions {
+        warmup: usize,
+    },
+    /// Result of the tuning started by [`UiCommand::TuneOptions`]
+    TuningFinished(TuningResult),
+    /// Applies ML ELO options recommended by the last tuning
+    ApplyTunedOptions,
+    ListPairSynergies {
+        request_id: RequestId,
+        limit: usize,
+    },
+    RatingHistory {
+        request_id: RequestId,
+        query: RatingHistoryQuery,
+    },
+    PlayerStats {
+        request_id: RequestId,
+        player: StatsPlayer,
+    },
+    /// Heroes DotaBot offered for the match in progress, added to earlier offers
+    OfferedHeroes(HashMap<DiscordUsername, Vec<Hero>>),
+    /// Hero played in the match in progress
+    SetPlayedHero {
+        player: DiscordUsername,
+        hero: Hero,
+    },
+    CorrectMatch {
+        request_id: RequestId,
+        correction: MatchCorrection,
+    },
+    QueryHistory {
+        request_id: RequestId,
+        query: HistoryQuery,
+        /// Includes fake matches that no longer count for ratings
+        outdated_fakes: bool,
+    },
     CloseApplication,
 }
 
+/// Audited change of a recorded match.
+#[derive(Clone, Debug)]
+pub struct MatchCorrection {
+    pub id: MatchId,
+    pub author: String,
+    pub reason: String,
+    /// Voids the match when not set
+    pub amendment: Option<HistoryAmendment>,
+}
+
+/// Players to report relationship statistics for.
+#[derive(Clone, Debug)]
+pub enum StatsPlayer {
+    All,
+    Id(PlayerId),
+    Discord(DiscordUsername),
+}
+
+/// Ratings of players after every match in `[from, to)`.
+#[derive(Clone, Debug)]
+pub struct RatingHistoryQuery {
+    /// All players when empty
+    pub players: Vec<PlayerId>,
+    pub from: Option<DateTime<Local>>,
+    pub to: Option<DateTime<Local>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Backtest {
+    /// Options to evaluate, current ones when not set
+    pub options: Option<SpaweloOptions>,
+    pub warmup: usize,
+}
+
 #[derive(Clone, Debug)]
 pub enum FinishMatch {
     Cancelled,
     Finished(MatchInfo),
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct MatchInfo {
     pub winner: Team,
     pub scale: WinScale,
     pub duration: Duration,
     pub fake: bool,
+    /// Heroes played, in addition to ones set with `SetPlayedHero`
+    pub heroes: HashMap<PlayerId, Hero>,
 }
@@ -1,6 +1,9 @@
 use eloelo_model::options::{DescribedOption, DescribedOptionsGroup, Options};
 use serde::{Deserialize, Serialize};
-use spawelo::{MlEloOptions, PityBonusOptions, SpaweloOptions};
+use spawelo::{
+    ConstraintsOptions, MlEloOptions, PityBonusOptions, RatingModelOptions, ShuffleOptions,
+    SpaweloOptions, TrueSkillOptions,
+};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -45,8 +48,12 @@ impl EloEloOptions {
     pub fn to_described_options_group_vec(&self) -> Vec<DescribedOptionsGroup> {
         vec![
             self.general.to_described_options_group(),
+            self.spawelo.rating_model.to_described_options_group(),
             self.spawelo.ml_elo.to_described_options_group(),
+            self.spawelo.true_skill.to_described_options_group(),
             self.spawelo.pity_bonus.to_described_options_group(),
+            self.spawelo.shuffle.to_described_options_group(),
+            self.spawelo.constraints.to_described_options_group(),
         ]
     }
 }
@@ -57,6 +64,10 @@ impl EloEloOptions {
 pub struct EloEloOptionsTransport {
     pub ml_elo: MlEloOptions,
     pub pity_bonus: PityBonusOptions,
+    pub rating_model: RatingModelOptions,
+    pub true_skill: TrueSkillOptions,
+    pub shuffle: ShuffleOptions,
+    pub constraints: ConstraintsOptions,
     pub general: GeneralOptions,
 }
 
@@ -67,6 +78,10 @@ impl Into<EloEloOptions> for EloEloOptionsTransport {
             spawelo: SpaweloOptions {
                 ml_elo: self.ml_elo,
                 pity_bonus: self.pity_bonus,
+                rating_model: self.rating_model,
+                true_skill: self.true_skill,
+                shuffle: self.shuffle,
+                constraints: self.constraints,
             },
         }
     }
@@ -7,16 +7,29 @@ use eloelo_model::player::PlayersConfig;
 use itertools::Itertools;
 use log::{debug, info, warn};
 use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
+use serde_json::Value;
 
 use super::config::Config;
 use super::elodisco::bot_state::BotState;
 use super::ui_state::State;
-use eloelo_model::history::{History, HistoryEntry};
+use eloelo_model::history::{
+    assign_missing_ids, History, HistoryAudit, HistoryCorrection, HistoryEntry,
+    HistorySerializeWrapper,
+};
+use eloelo_model::migration::{self, Schema, Versioned};
 use eloelo_model::GameId;
 
 const HISTORY_SUFFIX: &str = ".history.json";
+const AUDIT_SUFFIX: &str = ".audit.json";
 const HISTORY_GIT_DIR: &str = "history_git";
+const BACKUP_DIR: &str = "backups";
+
+#[derive(Clone, Copy)]
+enum Format {
+    Json,
+    Yaml,
+}
 
 fn state_file_path() -> PathBuf {
     data_dir().join("state.yaml")
@@ -49,14 +62,13 @@ pub fn load_state() -> Result<Option<State>> {
     if !state_file_path().exists() {
         return Ok(None);
     }
-    let state_file = File::open(state_file_path())?;
-    let state = serde_yaml::from_reader(state_file)?;
+    let state = load_versioned(&state_file_path(), &migration::STATE, Format::Yaml)?;
     Ok(Some(state))
 }
 
 pub fn store_state(state: &State) -> Result<()> {
     ensure_dir_created(&state_file_path())?;
-    store_file_with_backup(&state_file_path(), state)?;
+    store_file_with_backup(&state_file_path(), &migration::STATE, state)?;
     Ok(())
 }
 
@@ -66,14 +78,13 @@ pub fn load_bot_state() -> Result<BotState> {
     if !path.exists() {
         store_bot_state(&Default::default())?;
     }
-    let state_file = File::open(path)?;
-    Ok(serde_yaml::from_reader(state_file)?)
+    load_versioned(&path, &migration::BOT_STATE, Format::Yaml)
 }
 
 pub fn store_bot_state(state: &BotState) -> Result<()> {
     debug!("Storing bot state");
     ensure_dir_created(&bot_state_file_path())?;
-    store_file_with_backup(&bot_state_file_path(), state)?;
+    store_file_with_backup(&bot_state_file_path(), &migration::BOT_STATE, state)?;
     Ok(())
 }
 
@@ -93,8 +104,8 @@ pub fn load_players() -> Result<PlayersConfig> {
         info!("Players file does not exist, creating.");
         store_default_players_config()?;
     }
-    let config_file = File::open(players_file_path())?;
-    let config: PlayersConfig = serde_yaml::from_reader(config_file)?;
+    let config: PlayersConfig =
+        load_versioned(&players_file_path(), &migration::PLAYERS, Format::Yaml)?;
     let player_ids: String = config.players.iter().map(|p| &p.id).join(", ");
     let n = config.players.len();
 
@@ -135,23 +146,22 @@ pub fn store_default_config() -> Result<()> {
 
 pub fn store_default_players_config() -> Result<()> {
     ensure_dir_created(&players_file_path())?;
-    let config_file = File::create(&players_file_path())?;
-    Ok(serde_yaml::to_writer(
-        config_file,
+    write_versioned(
+        &players_file_path(),
+        &migration::PLAYERS,
+        Format::Yaml,
         &PlayersConfig::example(),
-    )?)
+    )
 }
 
 pub fn store_players(players: PlayersConfig) -> Result<()> {
     ensure_dir_created(&players_file_path())?;
-    let config_file = File::create(&players_file_path())?;
-    Ok(serde_yaml::to_writer(config_file, &players)?)
-}
-
-#[derive(Serialize, Deserialize, PartialEq)]
-struct HistorySerializeWrapper {
-    game: GameId,
-    entries: Vec<HistoryEntry>,
+    write_versioned(
+        &players_file_path(),
+        &migration::PLAYERS,
+        Format::Yaml,
+        &players,
+    )
 }
 
 pub fn append_history_entry(game: &GameId, entry: &HistoryEntry) -> Result<()> {
@@ -161,16 +171,34 @@ pub fn append_history_entry(game: &GameId, entry: &HistoryEntry) -> Result<()> {
         vec![]
     };
     entries.push(entry.clone());
+    store_history_entries(game, entries)
+}
 
-    let out_file = File::create(&history_path(game))?;
-    serde_json::to_writer_pretty(
-        out_file,
+/// Replaces all history entries of the game.
+pub fn store_history_entries(game: &GameId, entries: Vec<HistoryEntry>) -> Result<()> {
+    write_versioned(
+        &history_path(game),
+        &migration::HISTORY,
+        Format::Json,
         &HistorySerializeWrapper {
             game: game.clone(),
             entries,
         },
-    )?;
-    Ok(())
+    )
+}
+
+pub fn append_history_correction(game: &GameId, correction: &HistoryCorrection) -> Result<()> {
+    let path = audit_path(game);
+    let mut audit = if path.is_file() {
+        load_versioned(&path, &migration::AUDIT, Format::Json)?
+    } else {
+        HistoryAudit {
+            game: game.clone(),
+            corrections: vec![],
+        }
+    };
+    audit.corrections.push(correction.clone());
+    write_versioned(&path, &migration::AUDIT, Format::Json, &audit)
 }
 
 pub fn history_dir() -> PathBuf {
@@ -195,8 +223,9 @@ pub fn load_history() -> Result<History> {
 }
 
 fn load_history_file(path: &Path) -> Result<HistorySerializeWrapper> {
-    let history_file = File::open(path)?;
-    let history: HistorySerializeWrapper = serde_json::from_reader(history_file)?;
+    let mut history: HistorySerializeWrapper =
+        load_versioned(path, &migration::HISTORY, Format::Json)?;
+    assign_missing_ids(&mut history.entries);
     Ok(history)
 }
 
@@ -208,12 +237,74 @@ fn is_regular_history_file(path: &Path) -> bool {
 }
 
 pub fn history_path(game: &GameId) -> PathBuf {
-    let safe_game_id = game.as_str().replace(" ", "_").replace(":", "_");
-    let filename = format!("{}{}", safe_game_id, HISTORY_SUFFIX);
-    history_dir().join(filename)
+    history_dir().join(format!("{}{}", safe_game_id(game), HISTORY_SUFFIX))
+}
+
+pub fn audit_path(game: &GameId) -> PathBuf {
+    history_dir().join(format!("{}{}", safe_game_id(game), AUDIT_SUFFIX))
+}
+
+fn safe_game_id(game: &GameId) -> String {
+    game.as_str().replace(" ", "_").replace(":", "_")
 }
 
-fn store_file_with_backup<T>(path: &Path, data: &T) -> Result<()>
+/// Loads a file, upgrading it in place when its schema version is older than the current one.
+/// The original is copied to the backup directory first.
+fn load_versioned<T>(path: &Path, schema: &Schema, format: Format) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let file = File::open(path)?;
+    let mut value: Value = match format {
+        Format::Json => serde_json::from_reader(file)?,
+        Format::Yaml => serde_yaml::from_reader(file)?,
+    };
+    let version = migration::schema_version(&value)?;
+    migration::ensure_supported(schema, version)
+        .with_context(|| format!("Cannot load {}", path.to_string_lossy()))?;
+    if version == schema.current_version() {
+        return Ok(serde_json::from_value(value)?);
+    }
+
+    backup(path, &format!(".v{version}.bak")).context("Cannot back up before migration")?;
+    migration::migrate(schema, &mut value)?;
+    let data = serde_json::from_value(value)?;
+    write_versioned(path, schema, format, &data)?;
+    Ok(data)
+}
+
+fn write_versioned<T: Serialize>(
+    path: &Path,
+    schema: &Schema,
+    format: Format,
+    data: &T,
+) -> Result<()> {
+    let out_file = File::create(path)?;
+    let versioned = Versioned::new(schema, data);
+    match format {
+        Format::Json => serde_json::to_writer_pretty(out_file, &versioned)?,
+        Format::Yaml => serde_yaml::to_writer(out_file, &versioned)?,
+    }
+    Ok(())
+}
+
+/// Copies the file to the backup directory, under its name followed by `suffix`.
+// Backups are kept outside of the history git mirror, which must not have untracked files.
+fn backup(path: &Path, suffix: &str) -> Result<()> {
+    let backup_dir = data_dir().join(BACKUP_DIR);
+    std::fs::create_dir_all(&backup_dir)?;
+    let filename = path
+        .file_name()
+        .map(OsStr::to_string_lossy)
+        .unwrap_or_default();
+    let backup_path = backup_dir.join(format!("{filename}{suffix}"));
+    std::fs::copy(path, &backup_path)
+        .with_context(|| format!("Cannot back up {}", path.to_string_lossy()))?;
+    debug!("Backup written to {}", backup_path.to_string_lossy());
+    Ok(())
+}
+
+fn store_file_with_backup<T>(path: &Path, schema: &Schema, data: &T) -> Result<()>
 where
     T: Serialize + DeserializeOwned + PartialEq,
 {
@@ -229,17 +320,10 @@ where
         return Ok(());
     }
     // We are about to overwrite this file: create backup
-    if path.is_file() {
-        let orig_filename = path
-            .file_name()
-            .map(OsStr::to_string_lossy)
-            .unwrap_or_default();
-        let backup_path = path.with_file_name(format!("{}{}", orig_filename, ".bak"));
-        std::fs::rename(path, backup_path)?;
+    if orig.is_some() {
+        backup(path, ".bak")?;
     }
-    let out_file = File::create(path)?;
-    serde_yaml::to_writer(out_file, data)?;
-    Ok(())
+    write_versioned(path, schema, Format::Yaml, data)
 }
 
 fn ensure_dir_created(path: &Path) -> Result<()> {
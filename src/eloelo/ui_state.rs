@@ -1,11 +1,14 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
 
 use eloelo_model::decimal::Decimal;
+use eloelo_model::hero::Hero;
 use eloelo_model::options::DescribedOptionsGroup;
 use serde::{Deserialize, Serialize};
+use spawelo::TeamSplit;
 
 use super::config::Game;
 use eloelo_model::history::HistoryEntry;
+use eloelo_model::history_query::HistoryCursor;
 use eloelo_model::{BalancedTeam, GameId, GameState, PlayerId};
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -26,6 +29,25 @@ pub struct State {
 
     #[serde(default)]
     pub shuffle_temperature: i32,
+
+    #[serde(default = "new_seed")]
+    pub seed: u64,
+
+    #[serde(default)]
+    pub match_heroes: MatchHeroes,
+}
+
+/// Heroes of the match in progress, stored in its history entry when it finishes.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchHeroes {
+    pub played: BTreeMap<PlayerId, Hero>,
+    pub offered: BTreeMap<PlayerId, Vec<Hero>>,
+}
+
+/// Seeds are kept below 2^32, so they survive a round trip through JS numbers.
+pub fn new_seed() -> u64 {
+    rand::random::<u32>().into()
 }
 
 impl State {
@@ -37,6 +59,8 @@ impl State {
             game_state: Default::default(),
             lobby: Default::default(),
             shuffle_temperature: Default::default(),
+            seed: new_seed(),
+            match_heroes: Default::default(),
         }
     }
 }
@@ -50,6 +74,7 @@ pub struct UiPlayer {
     pub elo: i32,
     pub present_in_lobby: bool,
     pub lose_streak: Option<i32>,
+    pub uncertainty: Option<i32>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -68,6 +93,9 @@ pub struct TeamPityBonus {
     pub pity_bonus_mul: f64,
     #[serde(default)]
     pub pity_bonus_add: i32,
+    /// Players whose lose streaks gave the team its pity bonus
+    #[serde(default)]
+    pub contributors: Vec<PlayerId>,
 }
 
 impl From<&BalancedTeam> for TeamPityBonus {
@@ -77,14 +105,17 @@ impl From<&BalancedTeam> for TeamPityBonus {
             pity_bonus_mul: value.pity_bonus_mul,
             pity_bonus_add: value.pity_bonus_add,
             real_elo: value.real_elo,
+            contributors: value.pity_contributors.clone(),
         }
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct UiHistory {
-    pub entries: HashMap<GameId, Vec<UiHistoryEntry>>,
+pub struct UiHistoryPage {
+    pub game: GameId,
+    pub entries: Vec<UiHistoryEntry>,
+    pub next_cursor: Option<HistoryCursor>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -114,11 +145,21 @@ pub struct UiState {
     pub reserve_players: Vec<UiPlayer>,
     pub win_prediction: Decimal,
     pub shuffle_temperature: i32,
+    /// Reason why the last shuffle failed
+    pub shuffle_error: Option<String>,
+    /// Best splits found by the last shuffle, as long as they match current teams
+    pub shuffle_alternatives: Vec<TeamSplit>,
+    pub selected_shuffle: Option<usize>,
+    /// Seed of the last shuffle and hero assignment of the next match
+    pub seed: u64,
+    /// Fitted elo bonus of the left side, when enabled in ML ELO options
+    pub side_advantage: Option<i32>,
+    /// Changes whenever history does, so the UI knows to refetch it
+    pub history_revision: u64,
 
     pub pity_bonus: PityBonus,
 
     pub game_state: GameState,
 
-    pub history: UiHistory,
     pub options: Vec<DescribedOptionsGroup>,
 }
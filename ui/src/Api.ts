@@ -1,5 +1,5 @@
-import type { DiscordPlayerInfo, EloEloState } from "./model";
-import { parseEloEloState } from "./parse";
+import type { DiscordPlayerInfo, EloEloState, HistoryPage } from "./model";
+import { parseEloEloState, parseHistoryPage } from "./parse";
 
 export const invoke = async (command: string, args: object) => {
   console.info({ command, args });
@@ -16,6 +16,28 @@ export const invoke = async (command: string, args: object) => {
   }
 };
 
+export const fetchHistory = async (params: {
+  game: string;
+  player?: string;
+  cursor?: string;
+  limit?: number;
+}): Promise<HistoryPage | undefined> => {
+  const query = new URLSearchParams();
+  for (const [key, value] of Object.entries(params)) {
+    if (value !== undefined) {
+      query.set(key, String(value));
+    }
+  }
+  const url = `${location.href}api/v1/history?${query}`;
+  const response = await fetch(url);
+  if (!response.ok) {
+    const status = response.status;
+    console.error({ status, body: await response.text() });
+    return undefined;
+  }
+  return parseHistoryPage(await response.json());
+};
+
 export async function connectToUiStream(options: {
   onError: (error: string) => void;
   onUiState: (state: EloEloState) => void;
@@ -6,6 +6,7 @@ import PersonOffIcon from "@mui/icons-material/PersonOff";
 import RocketLaunchIcon from "@mui/icons-material/RocketLaunch";
 import ShuffleIcon from "@mui/icons-material/Shuffle";
 import {
+  Alert,
   Box,
   Button,
   ButtonGroup,
@@ -36,6 +37,7 @@ import {
   type EloEloState,
   type GameState,
   type Team,
+  type TeamSplit,
   extractAvatars,
 } from "./model";
 import { useColorMode } from "./useColorMode";
@@ -98,7 +100,8 @@ function EloElo({
         size="large"
       >
         <HistoryView
-          history={getHistoryForCurrentGame(state)}
+          game={state.selectedGame}
+          historyRevision={state.historyRevision}
           avatars={extractAvatars(discordInfo)}
           players={state.reservePlayers.concat(
             state.rightPlayers,
@@ -293,6 +296,35 @@ function LobbyCallSplitButton() {
   );
 }
 
+function ShuffleAlternatives({
+  alternatives,
+  selected,
+}: {
+  alternatives: TeamSplit[];
+  selected?: number;
+}) {
+  if (alternatives.length < 2) {
+    return null;
+  }
+  return (
+    <Stack direction="row" justifyContent="center">
+      <ButtonGroup size="small">
+        {alternatives.map((split, index) => (
+          <Button
+            key={index}
+            variant={index === selected ? "contained" : "outlined"}
+            onClick={async () => {
+              await invoke("select_shuffle", { index });
+            }}
+          >
+            {`Δ${split.eloDiff} (${Math.round(split.winChance * 100)}%)`}
+          </Button>
+        ))}
+      </ButtonGroup>
+    </Stack>
+  );
+}
+
 function ShuffleTemperatureSelector() {
   return (
     <Grid container>
@@ -364,6 +396,20 @@ function MainView({
           });
         }}
       />
+      {state.shuffleError && (
+        <Alert severity="error">{state.shuffleError}</Alert>
+      )}
+      {state.gameState === "assemblingTeams" && (
+        <ShuffleAlternatives
+          alternatives={state.shuffleAlternatives}
+          selected={state.selectedShuffle}
+        />
+      )}
+      <Typography variant="caption" align="center" color="text.secondary">
+        Seed: {state.seed}
+        {state.sideAdvantage != null &&
+          ` · Left side advantage: ${state.sideAdvantage}`}
+      </Typography>
       <ShuffleTemperatureSelector />
 
       <ReserveList
@@ -380,11 +426,6 @@ function MainView({
   );
 }
 
-function getHistoryForCurrentGame(state: EloEloState) {
-  const maybeHistory = state.history.entries[state.selectedGame];
-  return maybeHistory === undefined ? [] : maybeHistory;
-}
-
 const initialEloEloState: EloEloState = {
   availableGames: [],
   selectedGame: "",
@@ -392,10 +433,12 @@ const initialEloEloState: EloEloState = {
   rightPlayers: [],
   reservePlayers: [],
   gameState: "assemblingTeams",
-  history: { entries: {} },
   pityBonus: undefined,
   options: [],
   shuffleTemperature: 0,
+  shuffleAlternatives: [],
+  seed: 0,
+  historyRevision: 0,
 };
 
 export default function App() {
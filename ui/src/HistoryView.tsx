@@ -1,6 +1,7 @@
 import {
   Avatar,
   Box,
+  Button,
   Paper,
   Stack,
   Table,
@@ -13,6 +14,7 @@ import {
   Typography,
 } from "@mui/material";
 import React from "react";
+import { fetchHistory } from "./Api";
 import { elapsedSecondsString } from "./Duration";
 import type { Avatars, HistoryEntry, Player } from "./model";
 
@@ -26,13 +28,46 @@ function percentage(v: number): string {
 }
 
 export function HistoryView({
-  history,
+  game,
+  historyRevision,
   players,
   avatars,
-}: { history: HistoryEntry[]; players: Player[]; avatars: Avatars }) {
+}: {
+  game: string;
+  historyRevision: number;
+  players: Player[];
+  avatars: Avatars;
+}) {
   const [highlightState, setHighlightState] = React.useState<
     string | undefined
   >(undefined);
+  const [history, setHistory] = React.useState<HistoryEntry[]>([]);
+  const [nextCursor, setNextCursor] = React.useState<string | undefined>(
+    undefined,
+  );
+
+  React.useEffect(() => {
+    let current = true;
+    setHistory([]);
+    setNextCursor(undefined);
+    fetchHistory({ game }).then((page) => {
+      if (current && page !== undefined) {
+        setHistory(page.entries);
+        setNextCursor(page.nextCursor);
+      }
+    });
+    return () => {
+      current = false;
+    };
+  }, [game, historyRevision]);
+
+  const loadMore = async () => {
+    const page = await fetchHistory({ game, cursor: nextCursor });
+    if (page !== undefined) {
+      setHistory((history) => history.concat(page.entries));
+      setNextCursor(page.nextCursor);
+    }
+  };
 
   const onAvatarClick = (player: string) => {
     setHighlightState((current) => (current === player ? undefined : player));
@@ -50,6 +85,7 @@ export function HistoryView({
             discordUsername: undefined,
             presentInLobby: false,
             loseStreak: 0,
+            uncertainty: undefined,
           };
     });
   };
@@ -87,6 +123,11 @@ export function HistoryView({
           ))}
         </TableBody>
       </Table>
+      {nextCursor !== undefined && (
+        <Box display="flex" justifyContent="center" p={1}>
+          <Button onClick={loadMore}>Load more</Button>
+        </Box>
+      )}
     </TableContainer>
   );
 
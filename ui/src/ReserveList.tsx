@@ -23,6 +23,7 @@ import {
 import { useState } from "react";
 import { invoke } from "./Api";
 import type { Avatars, DiscordPlayerInfo, Player, PlayerAvatar } from "./model";
+import { formatElo } from "./model";
 
 const Header = styled(Box)(({ theme }) => ({
   ...theme.typography.h6,
@@ -179,7 +180,7 @@ export function ReserveList({
         <ListItemAvatar>
           <Avatar src={avatarUrl} />
         </ListItemAvatar>
-        <ListItemText primary={player.name} secondary={player.elo} />
+        <ListItemText primary={player.name} secondary={formatElo(player)} />
         <AddLeftButton playerKey={player.id} disabled={!assemblingTeams} />
         <AddRightButton playerKey={player.id} disabled={!assemblingTeams} />
         {editable && <DeleteButton playerKey={player.id} />}
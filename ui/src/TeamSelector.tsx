@@ -27,6 +27,7 @@ import type {
   Side,
   TeamPityBonus,
 } from "./model";
+import { formatElo } from "./model";
 
 function MoveButton({
   side,
@@ -89,7 +90,7 @@ function PlayerProfile({
       </ListItemAvatar>
       <ListItemText
         primary={`${crown ? "👑 " : ""}${player.name}`}
-        secondary={player.elo}
+        secondary={formatElo(player)}
         sx={textSx}
       />
       {player.loseStreak != null && (
@@ -281,6 +282,9 @@ function TeamRoster({
   } else if (bonusAdd) {
     pityDescr = `${pityElo} with ${diffFormatter.format(bonusAdd)} pity bonus`;
   }
+  if (pityBonus && pityBonus.contributors.length > 0) {
+    pityDescr += ` (${pityBonus.contributors.join(", ")})`;
+  }
 
   return (
     <Paper sx={{ width: "100%", maxWidth: "500px" }}>
@@ -5,8 +5,15 @@ export type Player = {
   elo: number;
   presentInLobby: boolean;
   loseStreak: number | undefined;
+  uncertainty: number | undefined;
 };
 
+export function formatElo(player: Player): string {
+  return player.uncertainty != null
+    ? `${player.elo} ±${player.uncertainty}`
+    : `${player.elo}`;
+}
+
 export type GameState = "assemblingTeams" | "matchInProgress";
 
 export type Team = "left" | "right";
@@ -19,12 +26,17 @@ export type Game = {
 
 export type HistoryEntry = {
   entry: {
+    id?: string;
     timestamp: Date;
     winner: string[];
     loser: string[];
     duration: number;
     scale: WinScale;
     fake: boolean;
+    seed?: number;
+    winner_side?: "left" | "right";
+    heroes?: Record<string, string>;
+    offered_heroes?: Record<string, string[]>;
   };
   metadata: {
     winnerElo: number;
@@ -33,11 +45,13 @@ export type HistoryEntry = {
   };
 };
 
-export type History = {
-  entries: { [key: string]: HistoryEntry[] };
+export type HistoryPage = {
+  game: string;
+  entries: HistoryEntry[];
+  nextCursor?: string;
 };
 
-export type OptionType = "integer" | "decimal" | "string" | "boolean";
+export type OptionType = "integer" | "decimal" | "text" | "boolean";
 
 export type DescribedOption = {
   name: string;
@@ -59,11 +73,22 @@ export type EloEloState = {
   rightPlayers: Player[];
   reservePlayers: Player[];
   gameState: GameState;
-  history: History;
   pityBonus: PityBonus | undefined;
   options: OptionsGroup[];
   winPrediction?: number;
   shuffleTemperature: number;
+  shuffleError?: string;
+  shuffleAlternatives: TeamSplit[];
+  selectedShuffle?: number;
+  seed: number;
+  sideAdvantage?: number;
+  historyRevision: number;
+};
+
+export type TeamSplit = {
+  eloDiff: number;
+  winChance: number;
+  noveltyPenalty: number;
 };
 
 export type PityBonus = {
@@ -76,6 +101,7 @@ export type TeamPityBonus = {
   pityBonusAdd: number;
   realElo: number;
   pityElo: number;
+  contributors: string[];
 };
 
 export type PlayerAvatar = { username: string; avatarUrl: string | undefined };
@@ -2,16 +2,18 @@ import type {
   EloEloState,
   Game,
   GameState,
-  History,
   HistoryEntry,
+  HistoryPage,
   OptionsGroup,
   PityBonus,
   Player,
+  TeamSplit,
   WinScale,
 } from "./model";
 
 type HistoryEntryTransport = {
   entry: {
+    id?: string;
     winner: string[];
     loser: string[];
     timestamp: string;
@@ -19,6 +21,10 @@ type HistoryEntryTransport = {
     duration: number;
     scale: WinScale;
     fake: boolean;
+    seed?: number;
+    winner_side?: "left" | "right";
+    heroes?: Record<string, string>;
+    offered_heroes?: Record<string, string[]>;
   };
   metadata: {
     winnerElo: number;
@@ -27,8 +33,10 @@ type HistoryEntryTransport = {
   };
 };
 
-type HistoryTransport = {
-  entries: { [key: string]: HistoryEntryTransport[] };
+export type HistoryPageTransport = {
+  game: string;
+  entries: HistoryEntryTransport[];
+  nextCursor?: string;
 };
 
 export type EloEloStateTransport = {
@@ -38,11 +46,16 @@ export type EloEloStateTransport = {
   rightPlayers: Player[];
   reservePlayers: Player[];
   gameState: GameState;
-  history: HistoryTransport;
   pityBonus: PityBonus | undefined;
   options: OptionsGroup[];
   winPrediction: number;
   shuffleTemperature: number;
+  shuffleError?: string;
+  shuffleAlternatives: TeamSplit[];
+  selectedShuffle?: number;
+  seed: number;
+  sideAdvantage?: number;
+  historyRevision: number;
 };
 
 function parseHistoryEntry(historyEntry: HistoryEntryTransport): HistoryEntry {
@@ -58,25 +71,13 @@ function parseHistoryEntry(historyEntry: HistoryEntryTransport): HistoryEntry {
   };
 }
 
-function parseHistoryForSingleGame(
-  history: HistoryEntryTransport[],
-): HistoryEntry[] {
-  return history.map(parseHistoryEntry);
-}
-
-function parseHistory(historyTransport: HistoryTransport): History {
-  const entries = Object.fromEntries(
-    Object.entries(historyTransport.entries).map(([k, v]) => [
-      k,
-      parseHistoryForSingleGame(v),
-    ]),
-  );
-  return { entries };
+export function parseHistoryPage(page: HistoryPageTransport): HistoryPage {
+  const { game, entries, nextCursor } = page;
+  return { game, entries: entries.map(parseHistoryEntry), nextCursor };
 }
 
 export function parseEloEloState(
   eloEloState: EloEloStateTransport,
 ): EloEloState {
-  const { history, ...state } = eloEloState;
-  return { history: parseHistory(history), ...state };
+  return eloEloState;
 }
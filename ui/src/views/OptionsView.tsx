@@ -45,7 +45,7 @@ function castTextFieldValue(type: OptionType, value: string): Value {
       return value;
     case "integer":
       return Number.parseInt(value);
-    case "string":
+    case "text":
       return value;
     default:
       return "INVALID";